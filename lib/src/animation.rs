@@ -0,0 +1,296 @@
+//! Editing the frames and delays of animated states.
+
+use image::RgbaImage;
+
+use crate::dmi::{Dmi, DmiError, DmiResult, State};
+
+/// Splits a `x,y,index` hotspot where `index` is the 1-based image of the state,
+/// counted frame by frame with every direction of a frame in a row.
+pub(crate) fn hotspot_parts(hotspot: &str) -> Option<(&str, u32)> {
+    let (position, index) = hotspot.rsplit_once(',')?;
+    let index = index.trim().parse::<u32>().ok()?;
+    Some((position, index.checked_sub(1)?))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DedupeReport {
+    pub frames_before: u32,
+    pub frames_after: u32,
+    /// Frames merged into one, as the first frame and the number of frames.
+    pub runs: Vec<(u32, u32)>,
+}
+
+impl State {
+    /// The images of a frame with the hotspots on it, for comparing frames.
+    fn frame_content(&self, frame: u32) -> (Vec<RgbaImage>, Vec<(String, u32)>) {
+        let images = (0..self.dirs)
+            .map(|dir| self.frames[(frame * self.dirs + dir) as usize].to_rgba8())
+            .collect();
+
+        let mut hotspots = self
+            .hotspots
+            .iter()
+            .filter_map(|hotspot| hotspot_parts(hotspot))
+            .filter(|(_, index)| index / self.dirs == frame)
+            .map(|(position, index)| (position.trim().to_string(), index % self.dirs))
+            .collect::<Vec<_>>();
+        hotspots.sort();
+
+        (images, hotspots)
+    }
+    /// Groups consecutive frames that are identical in every direction, hotspots included.
+    /// A rewinding animation plays its first and last frames a different number of times
+    /// than the others, so those two always stay in runs of their own.
+    fn identical_runs(&self) -> Vec<(u32, u32)> {
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        let mut previous = None;
+
+        for frame in 0..self.frame_count {
+            let content = self.frame_content(frame);
+            let joinable = !self.rewind || (frame != 1 && frame + 1 != self.frame_count);
+            match runs.last_mut() {
+                Some((_, length)) if joinable && previous.as_ref() == Some(&content) => {
+                    *length += 1
+                }
+                _ => runs.push((frame, 1)),
+            }
+            previous = Some(content);
+        }
+
+        runs
+    }
+    /// Reports which frames `dedupe_frames` would merge without changing the state.
+    pub fn duplicate_frames(&self) -> DedupeReport {
+        self.dedupe_report(&self.identical_runs())
+    }
+    fn dedupe_report(&self, runs: &[(u32, u32)]) -> DedupeReport {
+        DedupeReport {
+            frames_before: self.frame_count,
+            frames_after: runs.len() as u32,
+            runs: runs
+                .iter()
+                .copied()
+                .filter(|(_, length)| *length > 1)
+                .collect(),
+        }
+    }
+    /// Merges consecutive frames that are identical in every direction into one frame
+    /// with the summed delay, so the animation plays the same.
+    pub fn dedupe_frames(&mut self) -> DedupeReport {
+        let runs = self.identical_runs();
+        let report = self.dedupe_report(&runs);
+
+        if report.runs.is_empty() {
+            return report;
+        }
+
+        let delays = runs
+            .iter()
+            .map(|(first, length)| {
+                (*first..first + length)
+                    .map(|frame| self.delay(frame))
+                    .sum::<f32>()
+            })
+            .collect();
+        let sources = runs.iter().map(|(first, _)| *first).collect::<Vec<_>>();
+
+        self.pick_frames(&sources);
+        self.delays = delays;
+
+        report
+    }
+}
+
+impl State {
+    /// Replaces the frames with the given source frames in order, moving their hotspots along.
+    /// Delays are left to the caller.
+    fn pick_frames(&mut self, sources: &[u32]) {
+        let mut frames = Vec::with_capacity(sources.len() * self.dirs as usize);
+        let mut hotspots = Vec::new();
+
+        for (frame, source) in sources.iter().enumerate() {
+            let start = (source * self.dirs) as usize;
+            frames.extend_from_slice(&self.frames[start..start + self.dirs as usize]);
+
+            for hotspot in self.hotspots.iter() {
+                match hotspot_parts(hotspot) {
+                    Some((position, index)) if index / self.dirs == *source => {
+                        let index = frame as u32 * self.dirs + index % self.dirs + 1;
+                        hotspots.push(format!("{position},{index}"));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // Hotspots that do not point at an image are kept as they are.
+        hotspots.extend(
+            self.hotspots
+                .iter()
+                .filter(|hotspot| {
+                    hotspot_parts(hotspot)
+                        .is_none_or(|(_, index)| index / self.dirs >= self.frame_count)
+                })
+                .cloned(),
+        );
+
+        self.frames = frames;
+        self.frame_count = sources.len() as u32;
+        self.hotspots = hotspots;
+    }
+    fn delay(&self, frame: u32) -> f32 {
+        self.delays.get(frame as usize).copied().unwrap_or(1.)
+    }
+    /// Length of one pass of the animation in ticks, frames without a delay count as 1.
+    pub fn total_ticks(&self) -> f32 {
+        (0..self.frame_count).map(|frame| self.delay(frame)).sum()
+    }
+    /// Frame shown at the time, in ticks from the start of the animation.
+    fn frame_at(&self, time: f32) -> u32 {
+        let mut end = 0.;
+        for frame in 0..self.frame_count {
+            end += self.delay(frame);
+            // Small margin so float sums landing just short of a frame border pick the next frame.
+            if time < end - 1e-4 {
+                return frame;
+            }
+        }
+        self.frame_count.saturating_sub(1)
+    }
+    /// Scales the delays so one pass of the animation takes `ticks`.
+    pub fn retime(&mut self, ticks: f32) -> DmiResult<()> {
+        if !(ticks > 0. && ticks.is_finite()) {
+            return Err(DmiError::InvalidAnimationLength(ticks));
+        }
+        if self.frame_count == 0 {
+            return Err(DmiError::NoFrames);
+        }
+
+        let total = self.total_ticks();
+        if total <= 0. {
+            return Err(DmiError::ZeroLengthAnimation);
+        }
+
+        let scale = ticks / total;
+        self.delays = (0..self.frame_count)
+            .map(|frame| self.delay(frame) * scale)
+            .collect();
+
+        Ok(())
+    }
+    /// Changes the number of frames keeping the length of the animation. Every new frame
+    /// gets an equal delay and shows the frame that was playing halfway through it.
+    pub fn resample(&mut self, frame_count: u32) -> DmiResult<()> {
+        if frame_count == 0 {
+            return Err(DmiError::InvalidFrameCount(frame_count));
+        }
+        if self.frame_count == 0 {
+            return Err(DmiError::NoFrames);
+        }
+
+        let total = self.total_ticks();
+        let delay = total / frame_count as f32;
+        let sources = (0..frame_count)
+            .map(|frame| self.frame_at((frame as f32 + 0.5) * delay))
+            .collect::<Vec<_>>();
+
+        self.pick_frames(&sources);
+        self.delays = if frame_count > 1 {
+            vec![delay; frame_count as usize]
+        } else {
+            Vec::new()
+        };
+
+        Ok(())
+    }
+}
+
+impl State {
+    /// Frames of one pass of a rewinding animation. Looping animations go back without
+    /// repeating either end, so the loop plays the same. Animations that play once end on
+    /// the first frame, like BYOND does.
+    fn rewind_sources(frame_count: u32, loop_: u32) -> Vec<u32> {
+        let back_to = if loop_ == 1 { 0 } else { 1 };
+        (0..frame_count)
+            .chain((back_to..frame_count.saturating_sub(1)).rev())
+            .collect()
+    }
+    /// Expands a rewinding animation into explicit frames and clears the flag.
+    /// Returns whether the state changed.
+    pub fn bake_rewind(&mut self) -> bool {
+        if !self.rewind {
+            return false;
+        }
+
+        let sources = State::rewind_sources(self.frame_count, self.loop_);
+        let delays = sources.iter().map(|frame| self.delay(*frame)).collect();
+
+        self.pick_frames(&sources);
+        self.delays = delays;
+        self.rewind = false;
+
+        true
+    }
+    /// Folds frames that play forward and then back, as `bake_rewind` writes them, into the
+    /// forward half with the rewind flag. Returns whether the state changed.
+    pub fn detect_rewind(&mut self) -> bool {
+        if self.rewind {
+            return false;
+        }
+
+        let length = self.frame_count;
+        let forward = match self.loop_ {
+            // Playing once goes back to the first frame, so the frames are an odd palindrome
+            // of at least 2 forward frames.
+            1 if length >= 3 && !length.is_multiple_of(2) => length.div_ceil(2),
+            1 => return false,
+            // Looping animations need 3 forward frames, anything shorter rewinds to itself.
+            _ if length >= 4 && length.is_multiple_of(2) => length / 2 + 1,
+            _ => return false,
+        };
+
+        let sources = State::rewind_sources(forward, self.loop_);
+        let palindrome = sources.iter().enumerate().all(|(frame, source)| {
+            let frame = frame as u32;
+            self.delay(frame) == self.delay(*source)
+                && self.frame_content(frame) == self.frame_content(*source)
+        });
+
+        if !palindrome {
+            return false;
+        }
+
+        let forward_sources = (0..forward).collect::<Vec<_>>();
+        let delays = forward_sources
+            .iter()
+            .map(|frame| self.delay(*frame))
+            .collect();
+
+        self.pick_frames(&forward_sources);
+        self.delays = delays;
+        self.rewind = true;
+
+        true
+    }
+}
+
+impl Dmi {
+    /// Reports the states `dedupe_frames` would change, by index.
+    pub fn duplicate_frames(&self) -> Vec<(usize, DedupeReport)> {
+        self.states
+            .iter()
+            .enumerate()
+            .map(|(index, state)| (index, state.duplicate_frames()))
+            .filter(|(_, report)| !report.runs.is_empty())
+            .collect()
+    }
+    /// Dedupes the frames of every state, returning the reports of the changed states by index.
+    pub fn dedupe_frames(&mut self) -> Vec<(usize, DedupeReport)> {
+        self.states
+            .iter_mut()
+            .enumerate()
+            .map(|(index, state)| (index, state.dedupe_frames()))
+            .filter(|(_, report)| !report.runs.is_empty())
+            .collect()
+    }
+}
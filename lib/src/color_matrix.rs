@@ -0,0 +1,111 @@
+//! Colour matrices as used by BYOND's `MapColors()` and `atom.color`.
+
+use image::{DynamicImage, Rgba};
+
+use crate::dmi::{Dmi, DmiError, DmiResult, Scope, State};
+
+/// A colour matrix in BYOND's row layout, every row holds the contribution of one
+/// input channel (red, green, blue, alpha) to the output channels, the last row is
+/// the constant added to every pixel. Values are in the `0..=1` range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorMatrix {
+    pub rows: [[f32; 4]; 5],
+}
+
+impl ColorMatrix {
+    pub const IDENTITY: ColorMatrix = ColorMatrix {
+        rows: [
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+            [0., 0., 0., 0.],
+        ],
+    };
+
+    /// Builds a matrix from the list forms BYOND accepts:
+    /// 9 values (3x3), 12 values (3x3 with constants), 16 values (4x4) and 20 values (4x4 with constants).
+    pub fn from_values(values: &[f32]) -> DmiResult<Self> {
+        let (row_length, row_count) = match values.len() {
+            9 => (3, 3),
+            12 => (3, 4),
+            16 => (4, 4),
+            20 => (4, 5),
+            length => return Err(DmiError::InvalidColorMatrix(length)),
+        };
+
+        let mut matrix = Self::IDENTITY;
+
+        for (index, row) in values.chunks(row_length).take(row_count).enumerate() {
+            // Without an alpha row the constants come right after the blue row.
+            let index = if row_count == 4 && row_length == 3 && index == 3 {
+                4
+            } else {
+                index
+            };
+            matrix.rows[index][..row_length].copy_from_slice(row);
+        }
+
+        Ok(matrix)
+    }
+    pub fn apply_pixel(&self, pixel: Rgba<u8>) -> Rgba<u8> {
+        let input = pixel.0.map(|channel| channel as f32 / 255.);
+        let mut output = [0; 4];
+
+        for (channel, output) in output.iter_mut().enumerate() {
+            let value = (0..4)
+                .map(|row| input[row] * self.rows[row][channel])
+                .sum::<f32>()
+                + self.rows[4][channel];
+            *output = (value.clamp(0., 1.) * 255.).round() as u8;
+        }
+
+        Rgba(output)
+    }
+    pub fn apply_image(&self, image: &DynamicImage) -> DynamicImage {
+        let mut buffer = image.to_rgba8();
+        for pixel in buffer.pixels_mut() {
+            *pixel = self.apply_pixel(*pixel);
+        }
+        DynamicImage::ImageRgba8(buffer)
+    }
+}
+
+impl State {
+    pub fn map_colors(&mut self, matrix: &ColorMatrix) {
+        for frame in self.frames.iter_mut() {
+            *frame = matrix.apply_image(frame);
+        }
+    }
+}
+
+impl Dmi {
+    pub fn map_colors(&mut self, matrix: &ColorMatrix, scope: &Scope) {
+        for (index, state) in self.states.iter_mut().enumerate() {
+            if scope.contains_state(index) {
+                state.map_colors(matrix);
+            }
+        }
+    }
+    /// Applies the matrix to a copy of the state and inserts it right after the original.
+    /// Returns the index of the new state.
+    pub fn map_colors_as_new_state(
+        &mut self,
+        index: usize,
+        matrix: &ColorMatrix,
+        name: String,
+    ) -> DmiResult<usize> {
+        let mut state = self
+            .states
+            .get(index)
+            .ok_or(DmiError::MissingState)?
+            .clone();
+
+        state.name = name;
+        state.map_colors(matrix);
+
+        self.states.insert(index + 1, state);
+
+        Ok(index + 1)
+    }
+}
@@ -0,0 +1,271 @@
+//! Generation of smoothing junction states from corner templates.
+//!
+//! Junction states are named after the bitmask of the connected neighbours, using the
+//! bits of /tg/station's smoothing: north 1, south 2, east 4, west 8, north-east 16,
+//! south-east 32, south-west 64 and north-west 128.
+//!
+//! Every junction is put together from four quarters, each quarter is copied from the
+//! template state matching how that corner connects:
+//! - `convex`, neither neighbour next to the corner is connected
+//! - `horizontal`, only the east or west neighbour is connected
+//! - `vertical`, only the north or south neighbour is connected
+//! - `concave`, both are connected but the diagonal is not
+//! - `flat`, both neighbours and the diagonal are connected
+
+use image::{imageops, DynamicImage};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::dmi::{Direction, Dmi, DmiError, DmiResult, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JunctionSet {
+    /// Every one of the 256 bitmasks.
+    Full,
+    /// The 47 bitmasks where diagonals are only set next to two connected cardinals.
+    Reduced,
+}
+
+impl JunctionSet {
+    pub fn bitmasks(self) -> Vec<u8> {
+        (0..=255u8)
+            .filter(|bitmask| self == Self::Full || reduce_bitmask(*bitmask) == *bitmask)
+            .collect()
+    }
+}
+
+impl FromStr for JunctionSet {
+    type Err = DmiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" | "256" => Ok(Self::Full),
+            "reduced" | "47" => Ok(Self::Reduced),
+            _ => Err(DmiError::UnknownJunctionSet(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CornerPiece {
+    Convex,
+    Concave,
+    Horizontal,
+    Vertical,
+    Flat,
+}
+
+impl CornerPiece {
+    pub const ALL: [CornerPiece; 5] = [
+        Self::Convex,
+        Self::Concave,
+        Self::Horizontal,
+        Self::Vertical,
+        Self::Flat,
+    ];
+
+    /// Name of the template state holding the piece.
+    pub fn state_name(self) -> &'static str {
+        match self {
+            Self::Convex => "convex",
+            Self::Concave => "concave",
+            Self::Horizontal => "horizontal",
+            Self::Vertical => "vertical",
+            Self::Flat => "flat",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CutterConfig {
+    pub prefix: String,
+    /// Name of the generated states, `{prefix}` and `{bitmask}` are replaced.
+    pub naming: String,
+    pub set: JunctionSet,
+}
+
+impl Default for CutterConfig {
+    fn default() -> Self {
+        Self {
+            prefix: String::new(),
+            naming: "{prefix}-{bitmask}".to_string(),
+            set: JunctionSet::Reduced,
+        }
+    }
+}
+
+impl CutterConfig {
+    pub fn state_name(&self, bitmask: u8) -> String {
+        self.naming
+            .replace("{prefix}", &self.prefix)
+            .replace("{bitmask}", &bitmask.to_string())
+    }
+}
+
+impl Direction {
+    /// Bit of the direction in a smoothing junction bitmask.
+    pub fn junction_bit(self) -> u8 {
+        match self {
+            Direction::North => 1,
+            Direction::South => 2,
+            Direction::East => 4,
+            Direction::West => 8,
+            Direction::NorthEast => 16,
+            Direction::SouthEast => 32,
+            Direction::SouthWest => 64,
+            Direction::NorthWest => 128,
+        }
+    }
+}
+
+/// The corners of a junction, as their vertical, horizontal and diagonal neighbours
+/// and whether the quarter is on the right and bottom half of the icon.
+const CORNERS: [(Direction, Direction, Direction, bool, bool); 4] = [
+    (
+        Direction::North,
+        Direction::West,
+        Direction::NorthWest,
+        false,
+        false,
+    ),
+    (
+        Direction::North,
+        Direction::East,
+        Direction::NorthEast,
+        true,
+        false,
+    ),
+    (
+        Direction::South,
+        Direction::West,
+        Direction::SouthWest,
+        false,
+        true,
+    ),
+    (
+        Direction::South,
+        Direction::East,
+        Direction::SouthEast,
+        true,
+        true,
+    ),
+];
+
+/// Clears the diagonal bits that are not next to two connected cardinals.
+pub fn reduce_bitmask(bitmask: u8) -> u8 {
+    let connected = |dir: Direction| bitmask & dir.junction_bit() != 0;
+    let mut reduced = bitmask & 0b1111;
+
+    for (vertical, horizontal, diagonal, _, _) in CORNERS {
+        if connected(vertical) && connected(horizontal) && connected(diagonal) {
+            reduced |= diagonal.junction_bit();
+        }
+    }
+
+    reduced
+}
+
+/// The piece used for every corner of the junction, in the order of `CORNERS`.
+pub fn corner_pieces(bitmask: u8) -> [CornerPiece; 4] {
+    let connected = |dir: Direction| bitmask & dir.junction_bit() != 0;
+
+    CORNERS.map(|(vertical, horizontal, diagonal, _, _)| {
+        match (connected(vertical), connected(horizontal)) {
+            (false, false) => CornerPiece::Convex,
+            (false, true) => CornerPiece::Horizontal,
+            (true, false) => CornerPiece::Vertical,
+            (true, true) if connected(diagonal) => CornerPiece::Flat,
+            (true, true) => CornerPiece::Concave,
+        }
+    })
+}
+
+impl Dmi {
+    /// Builds every junction state of the set from a template holding one state per corner piece.
+    pub fn cut_junctions(&self, config: &CutterConfig) -> DmiResult<Dmi> {
+        let pieces = CornerPiece::ALL.map(|piece| {
+            self.states
+                .iter()
+                .find(|state| state.name == piece.state_name())
+                .ok_or_else(|| DmiError::StateNotFound(piece.state_name().to_string()))
+        });
+        let pieces = pieces.into_iter().collect::<DmiResult<Vec<_>>>()?;
+        if pieces.iter().any(|state| state.frame_count == 0) {
+            return Err(DmiError::MissingData);
+        }
+        let piece = |piece: CornerPiece| pieces[piece as usize];
+
+        // Animated templates keep the timing of the convex piece.
+        let timing = piece(CornerPiece::Convex);
+        let frame_count = pieces
+            .iter()
+            .map(|state| state.frame_count)
+            .max()
+            .unwrap_or(1);
+
+        let (half_width, half_height) = (self.width / 2, self.height / 2);
+
+        let mut dmi = Dmi::new(config.prefix.clone(), self.width, self.height);
+
+        for bitmask in config.set.bitmasks() {
+            let mut state = State::new_blank(config.state_name(bitmask), self.width, self.height);
+            state.frames.clear();
+            state.frame_count = frame_count;
+            state.loop_ = timing.loop_;
+            state.rewind = timing.rewind;
+            if timing.frame_count == frame_count {
+                state.delays = timing.delays.clone();
+            } else if frame_count > 1 {
+                state.delays = vec![1.; frame_count as usize];
+            }
+
+            let corners = corner_pieces(bitmask);
+
+            for frame in 0..frame_count {
+                let mut image = DynamicImage::new_rgba8(self.width, self.height);
+
+                for ((_, _, _, right, bottom), corner) in CORNERS.iter().zip(corners) {
+                    let source = piece(corner);
+                    // Pieces are read from their south facing images.
+                    let source =
+                        &source.frames[((frame % source.frame_count) * source.dirs) as usize];
+
+                    let (x, width) = if *right {
+                        (half_width, self.width - half_width)
+                    } else {
+                        (0, half_width)
+                    };
+                    let (y, height) = if *bottom {
+                        (half_height, self.height - half_height)
+                    } else {
+                        (0, half_height)
+                    };
+
+                    let quarter = source.crop_imm(x, y, width, height);
+                    imageops::replace(&mut image, &quarter, x as i64, y as i64);
+                }
+
+                state.frames.push(image);
+            }
+
+            dmi.states.push(state);
+        }
+
+        Ok(dmi)
+    }
+    /// Bitmasks of the set that have no state in the DMI.
+    pub fn missing_junctions(&self, config: &CutterConfig) -> Vec<u8> {
+        let names = self
+            .states
+            .iter()
+            .map(|state| state.name.as_str())
+            .collect::<HashSet<_>>();
+
+        config
+            .set
+            .bitmasks()
+            .into_iter()
+            .filter(|bitmask| !names.contains(config.state_name(*bitmask).as_str()))
+            .collect()
+    }
+}
@@ -8,12 +8,21 @@ use std::ffi::OsStr;
 use std::fs::{create_dir_all, remove_dir_all, File};
 use std::io::{BufWriter, Cursor, Read as _, Write as _};
 use std::path::Path;
+use std::str::FromStr;
 use thiserror::Error;
 
+use crate::lint::clear_transparent_pixels;
+use crate::scalers::ResizeMethod;
 use crate::utils::{find_directory, image_to_base64, optimal_size};
 
 const DMI_VERSION: &str = "4.0";
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SaveOptions {
+    /// Zeroes the RGB of fully transparent pixels, which BYOND never shows.
+    pub clear_transparent: bool,
+}
+
 #[derive(Debug)]
 pub struct Dmi {
     pub name: String,
@@ -217,6 +226,25 @@ impl Dmi {
     pub fn save<P>(&self, path: P) -> DmiResult<()>
     where
         P: AsRef<Path>,
+    {
+        self.save_with(path, &SaveOptions::default())
+    }
+    pub fn save_with<P>(&self, path: P, options: &SaveOptions) -> DmiResult<()>
+    where
+        P: AsRef<Path>,
+    {
+        if let Some(parent) = path.as_ref().parent() {
+            if !parent.exists() {
+                create_dir_all(parent)?;
+            }
+        }
+
+        self.write(BufWriter::new(File::create(path)?), options)
+    }
+    /// Encodes the DMI as PNG into the writer.
+    pub fn write<W>(&self, mut writer: W, options: &SaveOptions) -> DmiResult<()>
+    where
+        W: std::io::Write,
     {
         let total_frames = self
             .states
@@ -240,13 +268,10 @@ impl Dmi {
             }
         }
 
-        if let Some(parent) = path.as_ref().parent() {
-            if !parent.exists() {
-                create_dir_all(parent)?;
-            }
+        if options.clear_transparent {
+            clear_transparent_pixels(&mut image_buffer);
         }
 
-        let mut writer = BufWriter::new(File::create(path)?);
         let mut encoder = Encoder::new(&mut writer, width, height);
 
         encoder.set_compression(Compression::Best);
@@ -309,12 +334,14 @@ impl Dmi {
             states,
         })
     }
-    pub fn resize(&mut self, width: u32, height: u32, method: image::imageops::FilterType) {
-        self.width = width;
-        self.height = height;
+    pub fn resize(&mut self, width: u32, height: u32, method: ResizeMethod) -> DmiResult<()> {
         for state in self.states.iter_mut() {
-            state.resize(width, height, method);
+            state.resize(width, height, method)?;
         }
+        self.width = width;
+        self.height = height;
+
+        Ok(())
     }
     pub fn crop(&mut self, x: u32, y: u32, width: u32, height: u32) {
         self.width = width;
@@ -330,9 +357,69 @@ impl Dmi {
             state.expand(x, y, width, height);
         }
     }
+    /// Changes the canvas size around the anchor. Returns the number of hotspots removed
+    /// because they fell outside the new canvas.
+    pub fn resize_canvas(&mut self, width: u32, height: u32, anchor: Anchor) -> usize {
+        let (x, y) = anchor.offset(self.width, self.height, width, height);
+        self.width = width;
+        self.height = height;
+        self.states
+            .iter_mut()
+            .map(|state| state.resize_canvas(width, height, x, y))
+            .sum()
+    }
+    /// Replaces colours inside the scope and returns the number of changed pixels per state.
+    /// Nothing is written when `preview` is set, only the counts are returned.
+    pub fn replace_colors(
+        &mut self,
+        map: &[(Rgba<u8>, Rgba<u8>)],
+        tolerance: u8,
+        scope: &Scope,
+        preview: bool,
+    ) -> Vec<usize> {
+        self.states
+            .iter_mut()
+            .enumerate()
+            .map(|(index, state)| {
+                if scope.contains_state(index) {
+                    state.replace_colors(map, tolerance, scope, preview)
+                } else {
+                    0
+                }
+            })
+            .collect()
+    }
+    pub fn content_bounds(&self, scope: &Scope) -> Option<Rect> {
+        self.states
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| scope.contains_state(*index))
+            .filter_map(|(_, state)| state.content_bounds(scope))
+            .reduce(Rect::union)
+    }
+    /// Crops every state to the content bounds of the scope and returns the applied rectangle.
+    /// With `symmetric`, opposite margins are kept equal so the content stays centred.
+    pub fn trim(&mut self, symmetric: bool, scope: &Scope) -> Option<Rect> {
+        let mut bounds = self.content_bounds(scope)?;
+
+        if symmetric {
+            bounds = bounds.symmetric_within(self.width, self.height);
+        }
+
+        if bounds.width != self.width || bounds.height != self.height {
+            // Resizing the canvas moves the hotspots along and drops the ones left outside.
+            self.resize_canvas(
+                bounds.width,
+                bounds.height,
+                Anchor::Custom(-(bounds.x as i64), -(bounds.y as i64)),
+            );
+        }
+
+        Some(bounds)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct State {
     pub name: String,
     pub dirs: u32,
@@ -488,10 +575,14 @@ impl State {
             hotspots: state.hotspots,
         })
     }
-    pub fn resize(&mut self, width: u32, height: u32, method: imageops::FilterType) {
-        for frame in self.frames.iter_mut() {
-            *frame = frame.resize_exact(width, height, method);
-        }
+    pub fn resize(&mut self, width: u32, height: u32, method: ResizeMethod) -> DmiResult<()> {
+        self.frames = self
+            .frames
+            .iter()
+            .map(|frame| method.resize(frame, width, height))
+            .collect::<DmiResult<_>>()?;
+
+        Ok(())
     }
     pub fn crop(&mut self, x: u32, y: u32, width: u32, height: u32) {
         for frame in self.frames.iter_mut() {
@@ -505,6 +596,403 @@ impl State {
             *frame = bottom;
         }
     }
+    /// Places every frame at `x`, `y` on a new canvas, cropping whatever falls outside.
+    /// Hotspots that fall outside are removed, returns how many were removed.
+    pub fn resize_canvas(&mut self, width: u32, height: u32, x: i64, y: i64) -> usize {
+        for frame in self.frames.iter_mut() {
+            let mut bottom = DynamicImage::new_rgba8(width, height);
+            imageops::replace(&mut bottom, frame, x, y);
+            *frame = bottom;
+        }
+
+        let count = self.hotspots.len();
+        self.hotspots = self
+            .hotspots
+            .iter()
+            .filter_map(|hotspot| shift_hotspot(hotspot, x, y, width, height))
+            .collect();
+
+        count - self.hotspots.len()
+    }
+    pub fn replace_colors(
+        &mut self,
+        map: &[(Rgba<u8>, Rgba<u8>)],
+        tolerance: u8,
+        scope: &Scope,
+        preview: bool,
+    ) -> usize {
+        let mut count = 0;
+
+        for frame in 0..self.frame_count {
+            if !scope.contains_frame(frame) {
+                continue;
+            }
+            for direction in 0..self.dirs {
+                if !scope.contains_dir(direction) {
+                    continue;
+                }
+                let image = &mut self.frames[(frame * self.dirs + direction) as usize];
+                let mut buffer = image.to_rgba8();
+                let mut changed = false;
+
+                for pixel in buffer.pixels_mut() {
+                    let Some((_, to)) = map
+                        .iter()
+                        .find(|(from, _)| color_matches(*pixel, *from, tolerance))
+                    else {
+                        continue;
+                    };
+                    if pixel != to {
+                        count += 1;
+                        changed = true;
+                        *pixel = *to;
+                    }
+                }
+
+                if changed && !preview {
+                    *image = DynamicImage::ImageRgba8(buffer);
+                }
+            }
+        }
+
+        count
+    }
+    pub fn content_bounds(&self, scope: &Scope) -> Option<Rect> {
+        let mut bounds: Option<Rect> = None;
+
+        for frame in 0..self.frame_count {
+            if !scope.contains_frame(frame) {
+                continue;
+            }
+            for direction in 0..self.dirs {
+                if !scope.contains_dir(direction) {
+                    continue;
+                }
+                let image = &self.frames[(frame * self.dirs + direction) as usize];
+                if let Some(image_bounds) = image_content_bounds(image) {
+                    bounds = Some(match bounds {
+                        Some(bounds) => bounds.union(image_bounds),
+                        None => image_bounds,
+                    });
+                }
+            }
+        }
+
+        bounds
+    }
+    pub fn set_dirs(&mut self, dirs: u32) -> DmiResult<()> {
+        if !matches!(dirs, 1 | 4 | 8) {
+            return Err(DmiError::InvalidDirections);
+        }
+
+        if dirs == self.dirs {
+            return Ok(());
+        }
+
+        let mut frames = Vec::with_capacity((self.frame_count * dirs) as usize);
+
+        for frame in 0..self.frame_count {
+            for direction in 0..dirs {
+                let source = if direction < self.dirs { direction } else { 0 };
+                frames.push(self.frames[(frame * self.dirs + source) as usize].clone());
+            }
+        }
+
+        self.frames = frames;
+        self.dirs = dirs;
+
+        Ok(())
+    }
+    pub fn fill_direction(
+        &mut self,
+        target: Direction,
+        source: Direction,
+        transform: DirectionTransform,
+    ) -> DmiResult<()> {
+        if source.index() >= self.dirs {
+            return Err(DmiError::MissingDirection);
+        }
+
+        if target.index() >= self.dirs {
+            self.set_dirs(target.min_dirs())?;
+        }
+
+        for frame in 0..self.frame_count {
+            let source = &self.frames[(frame * self.dirs + source.index()) as usize];
+            let image = transform.apply(source)?;
+            self.frames[(frame * self.dirs + target.index()) as usize] = image;
+        }
+
+        Ok(())
+    }
+    pub fn generate_directions(&mut self, preset: DirectionPreset) -> DmiResult<()> {
+        match preset {
+            DirectionPreset::MirrorEastToWest => self.fill_direction(
+                Direction::West,
+                Direction::East,
+                DirectionTransform::FlipHorizontal,
+            ),
+            DirectionPreset::MirrorWestToEast => self.fill_direction(
+                Direction::East,
+                Direction::West,
+                DirectionTransform::FlipHorizontal,
+            ),
+            DirectionPreset::DiagonalsFromCardinal => {
+                for direction in Direction::DIAGONALS {
+                    self.fill_direction(
+                        direction,
+                        direction.cardinal_fallback(),
+                        DirectionTransform::None,
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+    pub fn union(self, other: Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+
+        Rect::new(x, y, right - x, bottom - y)
+    }
+    /// Grows the rectangle so its margins inside a `width` x `height` canvas are equal on opposite sides.
+    pub fn symmetric_within(self, width: u32, height: u32) -> Rect {
+        let horizontal = self.x.min(width - (self.x + self.width));
+        let vertical = self.y.min(height - (self.y + self.height));
+
+        Rect::new(
+            horizontal,
+            vertical,
+            width - horizontal * 2,
+            height - vertical * 2,
+        )
+    }
+}
+
+/// Where the current image is placed when the canvas size changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+    /// Offset of the current image inside the new canvas, may be negative.
+    Custom(i64, i64),
+}
+
+impl Anchor {
+    pub fn offset(self, width: u32, height: u32, new_width: u32, new_height: u32) -> (i64, i64) {
+        let horizontal = new_width as i64 - width as i64;
+        let vertical = new_height as i64 - height as i64;
+
+        let x = match self {
+            Self::TopLeft | Self::Left | Self::BottomLeft => 0,
+            Self::Top | Self::Center | Self::Bottom => horizontal / 2,
+            Self::TopRight | Self::Right | Self::BottomRight => horizontal,
+            Self::Custom(x, _) => x,
+        };
+
+        let y = match self {
+            Self::TopLeft | Self::Top | Self::TopRight => 0,
+            Self::Left | Self::Center | Self::Right => vertical / 2,
+            Self::BottomLeft | Self::Bottom | Self::BottomRight => vertical,
+            Self::Custom(_, y) => y,
+        };
+
+        (x, y)
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = DmiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top_left" => Ok(Self::TopLeft),
+            "top" => Ok(Self::Top),
+            "top_right" => Ok(Self::TopRight),
+            "left" => Ok(Self::Left),
+            "center" => Ok(Self::Center),
+            "right" => Ok(Self::Right),
+            "bottom_left" => Ok(Self::BottomLeft),
+            "bottom" => Ok(Self::Bottom),
+            "bottom_right" => Ok(Self::BottomRight),
+            _ => Err(DmiError::UnknownAnchor(s.to_string())),
+        }
+    }
+}
+
+/// Limits an operation to some states, frames and directions. `None` selects everything.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    pub states: Option<Vec<usize>>,
+    pub frames: Option<Vec<u32>>,
+    pub dirs: Option<Vec<Direction>>,
+}
+
+impl Scope {
+    pub fn contains_state(&self, index: usize) -> bool {
+        self.states
+            .as_ref()
+            .is_none_or(|states| states.contains(&index))
+    }
+    pub fn contains_frame(&self, frame: u32) -> bool {
+        self.frames
+            .as_ref()
+            .is_none_or(|frames| frames.contains(&frame))
+    }
+    pub fn contains_dir(&self, direction: u32) -> bool {
+        self.dirs
+            .as_ref()
+            .is_none_or(|dirs| dirs.iter().any(|dir| dir.index() == direction))
+    }
+}
+
+/// Directions in the order they are stored in a DMI file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    South,
+    North,
+    East,
+    West,
+    SouthEast,
+    SouthWest,
+    NorthEast,
+    NorthWest,
+}
+
+impl Direction {
+    pub const CARDINALS: [Direction; 4] = [
+        Direction::South,
+        Direction::North,
+        Direction::East,
+        Direction::West,
+    ];
+    pub const DIAGONALS: [Direction; 4] = [
+        Direction::SouthEast,
+        Direction::SouthWest,
+        Direction::NorthEast,
+        Direction::NorthWest,
+    ];
+
+    pub fn from_index(index: u32) -> Option<Direction> {
+        [Self::CARDINALS, Self::DIAGONALS]
+            .concat()
+            .get(index as usize)
+            .copied()
+    }
+    /// Index of the direction inside a frame of a state.
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+    /// Smallest `dirs` value a state needs to contain this direction.
+    pub fn min_dirs(self) -> u32 {
+        match self {
+            Direction::South => 1,
+            Direction::North | Direction::East | Direction::West => 4,
+            _ => 8,
+        }
+    }
+    /// The cardinal direction BYOND displays when a diagonal is missing.
+    pub fn cardinal_fallback(self) -> Direction {
+        match self {
+            Direction::SouthEast | Direction::NorthEast => Direction::East,
+            Direction::SouthWest | Direction::NorthWest => Direction::West,
+            cardinal => cardinal,
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = DmiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "south" => Ok(Self::South),
+            "north" => Ok(Self::North),
+            "east" => Ok(Self::East),
+            "west" => Ok(Self::West),
+            "southeast" => Ok(Self::SouthEast),
+            "southwest" => Ok(Self::SouthWest),
+            "northeast" => Ok(Self::NorthEast),
+            "northwest" => Ok(Self::NorthWest),
+            _ => Err(DmiError::UnknownDirection(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectionTransform {
+    None,
+    FlipHorizontal,
+    FlipVertical,
+    /// Clockwise rotations, only allowed for square icons when turning by 90 degrees.
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl DirectionTransform {
+    fn apply(self, image: &DynamicImage) -> DmiResult<DynamicImage> {
+        if matches!(self, Self::Rotate90 | Self::Rotate270) && image.width() != image.height() {
+            return Err(DmiError::NonSquareRotation);
+        }
+
+        Ok(match self {
+            Self::None => image.clone(),
+            Self::FlipHorizontal => image.fliph(),
+            Self::FlipVertical => image.flipv(),
+            Self::Rotate90 => image.rotate90(),
+            Self::Rotate180 => image.rotate180(),
+            Self::Rotate270 => image.rotate270(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectionPreset {
+    MirrorEastToWest,
+    MirrorWestToEast,
+    DiagonalsFromCardinal,
+}
+
+impl FromStr for DirectionPreset {
+    type Err = DmiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mirror_east_to_west" => Ok(Self::MirrorEastToWest),
+            "mirror_west_to_east" => Ok(Self::MirrorWestToEast),
+            "diagonals_from_cardinal" => Ok(Self::DiagonalsFromCardinal),
+            _ => Err(DmiError::UnknownDirectionPreset(s.to_string())),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -541,7 +1029,7 @@ pub struct ClipboardState {
     pub hotspots: Vec<String>,
 }
 
-type DmiResult<T> = Result<T, DmiError>;
+pub(crate) type DmiResult<T> = Result<T, DmiError>;
 
 #[derive(Error, Debug)]
 #[error(transparent)]
@@ -554,6 +1042,8 @@ pub enum DmiError {
     ParseInt(#[from] std::num::ParseIntError),
     ParseFloat(#[from] std::num::ParseFloatError),
     DecodeError(#[from] base64::DecodeError),
+    Json(#[from] serde_json::Error),
+    Regex(#[from] regex::Error),
     #[error("Missing data")]
     MissingData,
     #[error("Missing ZTXT chunk")]
@@ -574,6 +1064,238 @@ pub enum DmiError {
     FindDirError,
     #[error("Directory does not exist")]
     DirDoesNotExist,
+    #[error("Directions must be 1, 4 or 8")]
+    InvalidDirections,
+    #[error("Rotating by 90 degrees needs a square icon")]
+    NonSquareRotation,
+    #[error("Direction is missing from the state")]
+    MissingDirection,
+    #[error("Unknown direction preset \"{0}\"")]
+    UnknownDirectionPreset(String),
+    #[error("Unknown anchor \"{0}\"")]
+    UnknownAnchor(String),
+    #[error(
+        "Unknown resize method \"{0}\", valid methods are {}",
+        ResizeMethod::NAMES.join(", ")
+    )]
+    UnknownResizeMethod(String),
+    #[error(
+        "{width}x{height} can not be scaled to {target_width}x{target_height}, the size must be multiplied by a power of {factor}"
+    )]
+    InvalidScaleSize {
+        width: u32,
+        height: u32,
+        target_width: u32,
+        target_height: u32,
+        factor: u32,
+    },
+    #[error("Unknown blend mode \"{0}\"")]
+    UnknownBlendMode(String),
+    #[error("Color matrix must have 9, 12, 16 or 20 values, got {0}")]
+    InvalidColorMatrix(usize),
+    #[error("State does not exist")]
+    MissingState,
+    #[error("Direction is not supported by this operation")]
+    UnsupportedDirection,
+    #[error("Colors must have 3 or 4 channels")]
+    InvalidColor,
+    #[error("Unknown direction \"{0}\"")]
+    UnknownDirection(String),
+    #[error("Unknown geometry mode \"{0}\"")]
+    UnknownGeometryMode(String),
+    #[error("State \"{0}\" not found")]
+    StateNotFound(String),
+    #[error("Recipe input \"{0}\" does not exist")]
+    MissingRecipeInput(String),
+    #[error("Recipe input \"{0}\" cannot be used here")]
+    InvalidRecipeInput(String),
+    #[error("Color matrix step needs an \"input\" or a \"matrix\"")]
+    MissingColorMatrix,
+    #[error("Expected {expected} delays, one for every frame, got {got}")]
+    DelayCountMismatch {
+        expected: usize,
+        got: usize,
+    },
+    #[error("Recipe input \"{input}\": {source}")]
+    RecipeInput {
+        input: String,
+        source: Box<DmiError>,
+    },
+    #[error("Recipe state \"{state}\", step {step} ({op}): {source}")]
+    RecipeStep {
+        state: String,
+        step: usize,
+        op: &'static str,
+        source: Box<DmiError>,
+    },
+    #[error(
+        "Recipe state \"{state}\" is {width}x{height}, expected {expected_width}x{expected_height}"
+    )]
+    RecipeSizeMismatch {
+        state: String,
+        width: u32,
+        height: u32,
+        expected_width: u32,
+        expected_height: u32,
+    },
+    #[error("Invalid hex color \"{0}\"")]
+    InvalidHexColor(String),
+    #[error("GAGS config expects {expected} colors, got {got}")]
+    GagsColorCount {
+        expected: usize,
+        got: usize,
+    },
+    #[error("GAGS color id {0} has no color")]
+    GagsMissingColor(usize),
+    #[error("GAGS icon state layer \"{0}\" can not have more than one color id")]
+    GagsTooManyColors(String),
+    #[error("Unknown GAGS reference \"{0}\"")]
+    GagsUnknownReference(String),
+    #[error("GAGS reference \"{0}\" references itself")]
+    GagsReferenceCycle(String),
+    #[error("Unknown junction set \"{0}\", valid sets are full and reduced")]
+    UnknownJunctionSet(String),
+    #[error("{width}x{height} can not be split into {tile_width}x{tile_height} tiles")]
+    InvalidTileSize {
+        width: u32,
+        height: u32,
+        tile_width: u32,
+        tile_height: u32,
+    },
+    #[error("State \"{0}\" is not named like a tile")]
+    InvalidTileName(String),
+    #[error("Tiles of \"{0}\" do not match the other states")]
+    TileGridMismatch(String),
+    #[error("Tile {x}, {y} of \"{state}\" is missing")]
+    MissingTile {
+        state: String,
+        x: u32,
+        y: u32,
+    },
+    #[error("Unknown conflict policy \"{0}\", valid policies are rename, replace, skip and error")]
+    UnknownConflictPolicy(String),
+    #[error("State \"{0}\" already exists")]
+    StateConflict(String),
+    #[error("Icon size {other_width}x{other_height} does not match {width}x{height}")]
+    DmiSizeMismatch {
+        width: u32,
+        height: u32,
+        other_width: u32,
+        other_height: u32,
+    },
+    #[error("GAGS state \"{state}\": {source}")]
+    Gags {
+        state: String,
+        source: Box<DmiError>,
+    },
+    #[error("Unknown sort order \"{0}\", valid orders are alphabetical and natural")]
+    UnknownSortOrder(String),
+    #[error("Palette has no colors")]
+    EmptyPalette,
+    #[error("Unknown palette format \"{0}\", valid formats are gpl, pal and act")]
+    UnknownPaletteFormat(String),
+    #[error("Invalid palette: {0}")]
+    InvalidPalette(String),
+    #[error("Palette has {0} colors, at most 256 fit in an ACT file")]
+    PaletteTooLarge(usize),
+    #[error("Animation length must be more than 0 ticks, got {0}")]
+    InvalidAnimationLength(f32),
+    #[error("Frame count must be at least 1, got {0}")]
+    InvalidFrameCount(u32),
+    #[error("State has no frames")]
+    NoFrames,
+    #[error("Animation has no length, every delay is 0")]
+    ZeroLengthAnimation,
+    #[error("Emissive mask is {mask_width}x{mask_height}, the state is {width}x{height}")]
+    EmissiveMaskSizeMismatch {
+        width: u32,
+        height: u32,
+        mask_width: u32,
+        mask_height: u32,
+    },
+}
+
+/// Whether every channel of the colours differs by at most `tolerance`.
+pub fn color_matches(a: Rgba<u8>, b: Rgba<u8>, tolerance: u8) -> bool {
+    a.0.iter()
+        .zip(b.0.iter())
+        .all(|(a, b)| a.abs_diff(*b) <= tolerance)
+}
+
+/// Moves a `x,y,frame` hotspot by the given offset. Returns `None` when it ends up outside
+/// the `width` x `height` canvas, hotspots that can not be parsed are kept as they are.
+pub(crate) fn shift_hotspot(
+    hotspot: &str,
+    x: i64,
+    y: i64,
+    width: u32,
+    height: u32,
+) -> Option<String> {
+    let mut parts = hotspot.split(',');
+
+    let (Some(Ok(hotspot_x)), Some(Ok(hotspot_y)), Some(frame)) = (
+        parts.next().map(|part| part.trim().parse::<i64>()),
+        parts.next().map(|part| part.trim().parse::<i64>()),
+        parts.next(),
+    ) else {
+        return Some(hotspot.to_string());
+    };
+
+    let (hotspot_x, hotspot_y) = (hotspot_x + x, hotspot_y + y);
+    if !(0..width as i64).contains(&hotspot_x) || !(0..height as i64).contains(&hotspot_y) {
+        return None;
+    }
+
+    Some(format!("{hotspot_x},{hotspot_y},{}", frame.trim()))
+}
+
+/// Moves a `x,y,frame` hotspot to the same place on a canvas scaled from `width` x `height`
+/// to `new_width` x `new_height`, hotspots that can not be parsed are kept as they are.
+pub(crate) fn scale_hotspot(
+    hotspot: &str,
+    width: u32,
+    height: u32,
+    new_width: u32,
+    new_height: u32,
+) -> String {
+    let mut parts = hotspot.split(',');
+
+    let (Some(Ok(x)), Some(Ok(y)), Some(frame)) = (
+        parts.next().map(|part| part.trim().parse::<u64>()),
+        parts.next().map(|part| part.trim().parse::<u64>()),
+        parts.next(),
+    ) else {
+        return hotspot.to_string();
+    };
+
+    if width == 0 || height == 0 {
+        return hotspot.to_string();
+    }
+
+    let x = x * new_width as u64 / width as u64;
+    let y = y * new_height as u64 / height as u64;
+
+    format!("{x},{y},{}", frame.trim())
+}
+
+fn image_content_bounds(image: &DynamicImage) -> Option<Rect> {
+    let image = image.to_rgba8();
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel[3] == 0 {
+            continue;
+        }
+        bounds = Some(match bounds {
+            Some((left, top, right, bottom)) => {
+                (left.min(x), top.min(y), right.max(x), bottom.max(y))
+            }
+            None => (x, y, x, y),
+        });
+    }
+
+    bounds
+        .map(|(left, top, right, bottom)| Rect::new(left, top, right - left + 1, bottom - top + 1))
 }
 
 fn save_image_as_bytes<P: AsRef<Path>>(image: &DynamicImage, path: P) -> DmiResult<()> {
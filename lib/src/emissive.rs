@@ -0,0 +1,137 @@
+//! Extraction of emissive overlay states, the glowing pixels of a state on a transparent background.
+
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use crate::dmi::{color_matches, Dmi, DmiError, DmiResult, State};
+
+pub const DEFAULT_EMISSIVE_THRESHOLD: u8 = 200;
+pub const DEFAULT_EMISSIVE_SUFFIX: &str = "_emissive";
+
+#[derive(Debug, Clone)]
+pub enum EmissiveSelector {
+    /// Pixels with at least the given luminance.
+    Brightness(u8),
+    /// Pixels matching any of the colours.
+    Colors {
+        colors: Vec<Rgba<u8>>,
+        tolerance: u8,
+    },
+    /// Pixels that are visible in the matching image of the mask state. A single direction
+    /// mask is used for every direction and its frames repeat if it has fewer.
+    Mask(State),
+}
+
+#[derive(Debug, Clone)]
+pub struct EmissiveOptions {
+    pub selector: EmissiveSelector,
+    /// Appended to the name of the source state.
+    pub suffix: String,
+    /// Colour of the selected pixels, the source colours are kept if `None`.
+    pub color: Option<Rgba<u8>>,
+}
+
+impl Default for EmissiveOptions {
+    fn default() -> Self {
+        Self {
+            selector: EmissiveSelector::Brightness(DEFAULT_EMISSIVE_THRESHOLD),
+            suffix: DEFAULT_EMISSIVE_SUFFIX.to_string(),
+            color: Some(Rgba([255, 255, 255, 255])),
+        }
+    }
+}
+
+fn luminance(pixel: Rgba<u8>) -> f32 {
+    0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32
+}
+
+impl State {
+    /// Returns a copy of the state holding only the selected pixels.
+    pub fn emissive(
+        &self,
+        selector: &EmissiveSelector,
+        color: Option<Rgba<u8>>,
+    ) -> DmiResult<State> {
+        if let EmissiveSelector::Mask(mask) = selector {
+            if mask.frame_count == 0 {
+                return Err(DmiError::MissingData);
+            }
+            if mask.dirs != 1 && mask.dirs < self.dirs {
+                return Err(DmiError::MissingDirection);
+            }
+        }
+
+        let mut state = self.clone();
+
+        for frame in 0..self.frame_count {
+            for direction in 0..self.dirs {
+                let index = (frame * self.dirs + direction) as usize;
+                let source = self.frames[index].to_rgba8();
+
+                let mask = match selector {
+                    EmissiveSelector::Mask(mask) => {
+                        let mask_direction = if mask.dirs == 1 { 0 } else { direction };
+                        let mask_frame = frame % mask.frame_count;
+                        let mask = mask.frames[(mask_frame * mask.dirs + mask_direction) as usize]
+                            .to_rgba8();
+                        if mask.dimensions() != source.dimensions() {
+                            return Err(DmiError::EmissiveMaskSizeMismatch {
+                                width: source.width(),
+                                height: source.height(),
+                                mask_width: mask.width(),
+                                mask_height: mask.height(),
+                            });
+                        }
+                        Some(mask)
+                    }
+                    _ => None,
+                };
+
+                let image = RgbaImage::from_fn(source.width(), source.height(), |x, y| {
+                    let pixel = *source.get_pixel(x, y);
+
+                    let selected = pixel[3] > 0
+                        && match selector {
+                            EmissiveSelector::Brightness(threshold) => {
+                                luminance(pixel) >= *threshold as f32
+                            }
+                            EmissiveSelector::Colors { colors, tolerance } => colors
+                                .iter()
+                                .any(|color| color_matches(pixel, *color, *tolerance)),
+                            EmissiveSelector::Mask(_) => mask
+                                .as_ref()
+                                .is_some_and(|mask| mask.get_pixel(x, y)[3] > 0),
+                        };
+
+                    match (selected, color) {
+                        (false, _) => Rgba([0, 0, 0, 0]),
+                        (true, Some(color)) => Rgba([color[0], color[1], color[2], pixel[3]]),
+                        (true, None) => pixel,
+                    }
+                });
+
+                state.frames[index] = DynamicImage::ImageRgba8(image);
+            }
+        }
+
+        Ok(state)
+    }
+}
+
+impl Dmi {
+    /// Inserts the emissive state of the state at `index` right after it.
+    /// Returns the index of the new state.
+    pub fn extract_emissive(
+        &mut self,
+        index: usize,
+        options: &EmissiveOptions,
+    ) -> DmiResult<usize> {
+        let source = self.states.get(index).ok_or(DmiError::MissingState)?;
+
+        let mut state = source.emissive(&options.selector, options.color)?;
+        state.name = format!("{}{}", source.name, options.suffix);
+
+        self.states.insert(index + 1, state);
+
+        Ok(index + 1)
+    }
+}
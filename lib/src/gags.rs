@@ -0,0 +1,380 @@
+//! Greyscale sprite configs (GAGS) as used by /tg/station.
+//!
+//! A config maps every output icon state to a list of layers, a layer is either an
+//! `icon_state` of the config's icon file, a `reference` to the output of another config,
+//! a `color_matrix` applied to everything rendered so far, or a nested list of layers
+//! that is rendered on its own and blended with the blend mode of its first layer.
+//!
+//! Colour ids are 1-based indexes into the colours the config is rendered with.
+
+use image::Rgba;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use crate::blend::BlendMode;
+use crate::color_matrix::ColorMatrix;
+use crate::dmi::{Dmi, DmiError, DmiResult, State};
+
+/// Configs that can be used by `reference` layers, keyed by their `reference_type`.
+pub type GagsReferences = HashMap<String, GagsConfig>;
+
+#[derive(Debug, Clone)]
+pub struct GagsConfig {
+    /// The DMI that `icon_state` layers are read from.
+    pub icon_file: PathBuf,
+    pub states: BTreeMap<String, Vec<GagsLayer>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum GagsLayer {
+    Group(Vec<GagsLayer>),
+    Layer(GagsLayerData),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GagsLayerData {
+    #[serde(flatten)]
+    pub kind: GagsLayerKind,
+    pub blend_mode: Option<String>,
+    #[serde(default)]
+    pub color_ids: Vec<GagsColorId>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GagsLayerKind {
+    IconState {
+        icon_state: String,
+    },
+    Reference {
+        reference_type: String,
+        #[serde(default)]
+        icon_state: String,
+    },
+    ColorMatrix {
+        color_matrix: Vec<GagsMatrixEntry>,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum GagsColorId {
+    Index(usize),
+    Color(String),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum GagsMatrixEntry {
+    Value(f32),
+    Row(Vec<f32>),
+    Color(String),
+}
+
+#[derive(Deserialize, Debug)]
+struct GagsReferenceFiles {
+    config: PathBuf,
+    icon: PathBuf,
+}
+
+/// Reads a JSON file mapping every `reference_type` to its config and icon file, like
+/// `{ "/datum/greyscale_config/stripe": { "config": "stripe.json", "icon": "stripe.dmi" } }`.
+/// Paths are relative to the directory of the file.
+pub fn open_gags_references<P>(path: P) -> DmiResult<GagsReferences>
+where
+    P: AsRef<Path>,
+{
+    let base = path.as_ref().parent().unwrap_or(Path::new("."));
+    let files: HashMap<String, GagsReferenceFiles> = serde_json::from_str(&read_to_string(&path)?)?;
+
+    files
+        .into_iter()
+        .map(|(reference_type, files)| {
+            let config = GagsConfig::open(base.join(files.config), base.join(files.icon))?;
+            Ok((reference_type, config))
+        })
+        .collect()
+}
+
+/// Parses a GAGS colour string such as `"#ff0000#00ff00"`.
+pub fn parse_gags_colors(colors: &str) -> DmiResult<Vec<Rgba<u8>>> {
+    colors
+        .split('#')
+        .filter(|color| !color.is_empty())
+        .map(|color| {
+            let invalid = || DmiError::InvalidHexColor(format!("#{color}"));
+            if !color.is_ascii() || (color.len() != 6 && color.len() != 8) {
+                return Err(invalid());
+            }
+            let mut channels = [255; 4];
+            for (index, channel) in channels.iter_mut().enumerate().take(color.len() / 2) {
+                *channel = u8::from_str_radix(&color[index * 2..index * 2 + 2], 16)
+                    .map_err(|_| invalid())?;
+            }
+            Ok(Rgba(channels))
+        })
+        .collect()
+}
+
+impl GagsConfig {
+    pub fn open<P, Q>(config: P, icon_file: Q) -> DmiResult<Self>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        Self::from_json(&read_to_string(config)?, icon_file)
+    }
+    pub fn from_json<P>(json: &str, icon_file: P) -> DmiResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self {
+            icon_file: icon_file.as_ref().to_path_buf(),
+            states: serde_json::from_str(json)?,
+        })
+    }
+    /// The number of colours the config has to be rendered with, the highest colour id in use.
+    pub fn expected_colors(&self) -> usize {
+        fn highest(layers: &[GagsLayer]) -> usize {
+            layers
+                .iter()
+                .map(|layer| match layer {
+                    GagsLayer::Group(layers) => highest(layers),
+                    GagsLayer::Layer(layer) => layer
+                        .color_ids
+                        .iter()
+                        .map(|id| match id {
+                            GagsColorId::Index(index) => *index,
+                            GagsColorId::Color(_) => 0,
+                        })
+                        .max()
+                        .unwrap_or(0),
+                })
+                .max()
+                .unwrap_or(0)
+        }
+
+        self.states
+            .values()
+            .map(|layers| highest(layers))
+            .max()
+            .unwrap_or(0)
+    }
+    /// Renders every state of the config into a new DMI the size of the icon file.
+    pub fn render(&self, colors: &[Rgba<u8>], references: &GagsReferences) -> DmiResult<Dmi> {
+        self.check_colors(colors)?;
+
+        let mut renderer = GagsRenderer::new(references);
+        let icon = renderer.icon(&self.icon_file)?;
+        let mut dmi = Dmi::new(icon.name.clone(), icon.width, icon.height);
+
+        for name in self.states.keys() {
+            dmi.states.push(renderer.render_state(self, name, colors)?);
+        }
+
+        Ok(dmi)
+    }
+    pub fn render_state(
+        &self,
+        name: &str,
+        colors: &[Rgba<u8>],
+        references: &GagsReferences,
+    ) -> DmiResult<State> {
+        self.check_colors(colors)?;
+        GagsRenderer::new(references).render_state(self, name, colors)
+    }
+    fn check_colors(&self, colors: &[Rgba<u8>]) -> DmiResult<()> {
+        let expected = self.expected_colors();
+        if colors.len() != expected {
+            return Err(DmiError::GagsColorCount {
+                expected,
+                got: colors.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+struct GagsRenderer<'a> {
+    references: &'a GagsReferences,
+    icons: HashMap<PathBuf, Dmi>,
+    /// Reference types being rendered, used to catch configs referencing themselves.
+    stack: Vec<String>,
+}
+
+impl<'a> GagsRenderer<'a> {
+    fn new(references: &'a GagsReferences) -> Self {
+        Self {
+            references,
+            icons: HashMap::new(),
+            stack: Vec::new(),
+        }
+    }
+    fn icon(&mut self, path: &Path) -> DmiResult<&Dmi> {
+        if !self.icons.contains_key(path) {
+            self.icons.insert(path.to_path_buf(), Dmi::open(path)?);
+        }
+        Ok(&self.icons[path])
+    }
+    fn render_state(
+        &mut self,
+        config: &GagsConfig,
+        name: &str,
+        colors: &[Rgba<u8>],
+    ) -> DmiResult<State> {
+        let layers = config
+            .states
+            .get(name)
+            .ok_or_else(|| DmiError::StateNotFound(name.to_string()))?;
+
+        let mut state = self
+            .render_group(config, layers, colors, None)
+            .map_err(|source| DmiError::Gags {
+                state: name.to_string(),
+                source: Box::new(source),
+            })?
+            .ok_or(DmiError::MissingData)?;
+
+        state.name = name.to_string();
+
+        Ok(state)
+    }
+    fn render_group(
+        &mut self,
+        config: &GagsConfig,
+        layers: &[GagsLayer],
+        colors: &[Rgba<u8>],
+        previous: Option<&State>,
+    ) -> DmiResult<Option<State>> {
+        let mut output: Option<State> = None;
+
+        for layer in layers {
+            let (image, data) = match layer {
+                GagsLayer::Group(group) => {
+                    let image =
+                        self.render_group(config, group, colors, output.as_ref().or(previous))?;
+                    // Groups are blended with the blend mode of their first layer.
+                    (image, first_layer(group))
+                }
+                GagsLayer::Layer(data) => (
+                    Some(self.render_layer(config, data, colors, output.as_ref().or(previous))?),
+                    Some(data),
+                ),
+            };
+
+            let Some(image) = image else {
+                continue;
+            };
+
+            match output.as_mut() {
+                None => output = Some(image),
+                Some(output) => {
+                    let mode = match data.and_then(|data| data.blend_mode.as_deref()) {
+                        Some(mode) => mode.to_lowercase().parse::<BlendMode>()?,
+                        None => BlendMode::Overlay,
+                    };
+                    output.blend(&image, mode, 1, 1)?;
+                }
+            }
+        }
+
+        Ok(output)
+    }
+    fn render_layer(
+        &mut self,
+        config: &GagsConfig,
+        layer: &GagsLayerData,
+        colors: &[Rgba<u8>],
+        previous: Option<&State>,
+    ) -> DmiResult<State> {
+        let layer_colors = layer
+            .color_ids
+            .iter()
+            .map(|id| match id {
+                GagsColorId::Index(index) => index
+                    .checked_sub(1)
+                    .and_then(|index| colors.get(index))
+                    .copied()
+                    .ok_or(DmiError::GagsMissingColor(*index)),
+                GagsColorId::Color(color) => parse_gags_colors(color)?
+                    .first()
+                    .copied()
+                    .ok_or_else(|| DmiError::InvalidHexColor(color.clone())),
+            })
+            .collect::<DmiResult<Vec<_>>>()?;
+
+        match &layer.kind {
+            GagsLayerKind::IconState { icon_state } => {
+                if layer_colors.len() > 1 {
+                    return Err(DmiError::GagsTooManyColors(icon_state.clone()));
+                }
+
+                let mut state = self
+                    .icon(&config.icon_file)?
+                    .states
+                    .iter()
+                    .find(|state| state.name == *icon_state)
+                    .ok_or_else(|| DmiError::StateNotFound(icon_state.clone()))?
+                    .clone();
+
+                if let Some(color) = layer_colors.first() {
+                    state.blend_color(*color, BlendMode::Multiply);
+                }
+
+                Ok(state)
+            }
+            GagsLayerKind::Reference {
+                reference_type,
+                icon_state,
+            } => {
+                if self.stack.contains(reference_type) {
+                    return Err(DmiError::GagsReferenceCycle(reference_type.clone()));
+                }
+
+                let reference = self
+                    .references
+                    .get(reference_type)
+                    .ok_or_else(|| DmiError::GagsUnknownReference(reference_type.clone()))?;
+                reference.check_colors(&layer_colors)?;
+
+                self.stack.push(reference_type.clone());
+                let state = self.render_state(reference, icon_state, &layer_colors);
+                self.stack.pop();
+
+                state
+            }
+            GagsLayerKind::ColorMatrix { color_matrix } => {
+                let mut values = Vec::new();
+                for entry in color_matrix {
+                    match entry {
+                        GagsMatrixEntry::Value(value) => values.push(*value),
+                        GagsMatrixEntry::Row(row) => values.extend(row),
+                        GagsMatrixEntry::Color(color) => {
+                            let rows = parse_gags_colors(color)?;
+                            let channels = if color.len() > 7 { 4 } else { 3 };
+                            for row in rows {
+                                values.extend(row.0[..channels].iter().map(|c| *c as f32 / 255.));
+                            }
+                        }
+                    }
+                }
+
+                let matrix = ColorMatrix::from_values(&values)?;
+                let mut state = previous.ok_or(DmiError::MissingData)?.clone();
+                state.map_colors(&matrix);
+
+                Ok(state)
+            }
+        }
+    }
+}
+
+fn first_layer(layers: &[GagsLayer]) -> Option<&GagsLayerData> {
+    match layers.first()? {
+        GagsLayer::Group(group) => first_layer(group),
+        GagsLayer::Layer(data) => Some(data),
+    }
+}
@@ -0,0 +1,289 @@
+//! Emulation of BYOND's `/icon` geometry procs: `Shift()`, `Turn()`, `Flip()` and `Scale()`.
+
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+use std::str::FromStr;
+
+use crate::dmi::{scale_hotspot, Direction, Dmi, DmiError, DmiResult, Scope, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryMode {
+    /// `Turn()` and `Flip()` also move the images between directions like BYOND does and
+    /// `Scale()` averages pixels when shrinking. Turns by angles other than multiples of 90
+    /// degrees sample the same way in both modes and do not match BYOND's resampling.
+    Byond,
+    /// Only transforms the images, directions stay where they are and scaling is nearest-neighbor.
+    Simple,
+}
+
+impl FromStr for GeometryMode {
+    type Err = DmiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "byond" => Ok(Self::Byond),
+            "simple" => Ok(Self::Simple),
+            _ => Err(DmiError::UnknownGeometryMode(s.to_string())),
+        }
+    }
+}
+
+/// Directions in clockwise order, starting from north.
+const CLOCKWISE: [Direction; 8] = [
+    Direction::North,
+    Direction::NorthEast,
+    Direction::East,
+    Direction::SouthEast,
+    Direction::South,
+    Direction::SouthWest,
+    Direction::West,
+    Direction::NorthWest,
+];
+
+impl Direction {
+    /// Unit offset of the direction in image coordinates, `y` grows downwards.
+    pub fn offset(self) -> (i64, i64) {
+        match self {
+            Direction::South => (0, 1),
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::West => (-1, 0),
+            Direction::SouthEast => (1, 1),
+            Direction::SouthWest => (-1, 1),
+            Direction::NorthEast => (1, -1),
+            Direction::NorthWest => (-1, -1),
+        }
+    }
+    /// Turns the direction clockwise by a multiple of 45 degrees.
+    pub fn turn(self, steps: i64) -> Direction {
+        let index = CLOCKWISE.iter().position(|dir| *dir == self).unwrap() as i64;
+        CLOCKWISE[(index + steps).rem_euclid(8) as usize]
+    }
+    /// Mirrors the direction horizontally (`horizontal`) or vertically.
+    pub fn mirror(self, horizontal: bool) -> Direction {
+        let (x, y) = self.offset();
+        let (x, y) = if horizontal { (-x, y) } else { (x, -y) };
+        Direction::from_offset(x, y).unwrap_or(self)
+    }
+    pub fn from_offset(x: i64, y: i64) -> Option<Direction> {
+        CLOCKWISE.into_iter().find(|dir| dir.offset() == (x, y))
+    }
+}
+
+pub fn shift_image(image: &RgbaImage, dir: Direction, offset: i64, wrap: bool) -> RgbaImage {
+    let (width, height) = (image.width() as i64, image.height() as i64);
+    let (dx, dy) = dir.offset();
+    let (dx, dy) = (dx * offset, dy * offset);
+
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let (source_x, source_y) = (x as i64 - dx, y as i64 - dy);
+        if wrap {
+            *image.get_pixel(
+                source_x.rem_euclid(width) as u32,
+                source_y.rem_euclid(height) as u32,
+            )
+        } else if source_x < 0 || source_y < 0 || source_x >= width || source_y >= height {
+            Rgba([0, 0, 0, 0])
+        } else {
+            *image.get_pixel(source_x as u32, source_y as u32)
+        }
+    })
+}
+
+/// Rotates clockwise around the centre of the image, keeping its size.
+/// Quarter turns of square images are exact, other angles use nearest-neighbor sampling
+/// and clip whatever leaves the canvas. This is not BYOND's resampling, so those turns
+/// differ from `Turn()` in DreamSeeker.
+pub fn turn_image(image: &RgbaImage, angle: f64) -> RgbaImage {
+    let angle = angle.rem_euclid(360.);
+
+    if image.width() == image.height() {
+        match angle {
+            0. => return image.clone(),
+            90. => return imageops::rotate90(image),
+            180. => return imageops::rotate180(image),
+            270. => return imageops::rotate270(image),
+            _ => {}
+        }
+    } else if angle == 0. {
+        return image.clone();
+    }
+
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let (center_x, center_y) = (image.width() as f64 / 2., image.height() as f64 / 2.);
+
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let (dx, dy) = (x as f64 + 0.5 - center_x, y as f64 + 0.5 - center_y);
+        let source_x = (dx * cos + dy * sin + center_x).floor();
+        let source_y = (-dx * sin + dy * cos + center_y).floor();
+        if source_x < 0.
+            || source_y < 0.
+            || source_x >= image.width() as f64
+            || source_y >= image.height() as f64
+        {
+            Rgba([0, 0, 0, 0])
+        } else {
+            *image.get_pixel(source_x as u32, source_y as u32)
+        }
+    })
+}
+
+/// BYOND's `Scale()`, nearest-neighbor when enlarging and averaging when shrinking.
+pub fn scale_image(image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    if width >= image.width() && height >= image.height() {
+        return imageops::resize(image, width, height, imageops::FilterType::Nearest);
+    }
+
+    let (scale_x, scale_y) = (
+        image.width() as f64 / width as f64,
+        image.height() as f64 / height as f64,
+    );
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let left = (x as f64 * scale_x).floor() as u32;
+        let top = (y as f64 * scale_y).floor() as u32;
+        let right = (((x + 1) as f64 * scale_x).ceil() as u32).clamp(left + 1, image.width());
+        let bottom = (((y + 1) as f64 * scale_y).ceil() as u32).clamp(top + 1, image.height());
+
+        // Colours are weighted by alpha so transparent pixels do not darken the edges.
+        let (mut sum, mut alpha, mut count) = ([0u64; 3], 0u64, 0u64);
+        for source_y in top..bottom {
+            for source_x in left..right {
+                let pixel = image.get_pixel(source_x, source_y);
+                for (channel, sum) in sum.iter_mut().enumerate() {
+                    *sum += pixel[channel] as u64 * pixel[3] as u64;
+                }
+                alpha += pixel[3] as u64;
+                count += 1;
+            }
+        }
+
+        if alpha == 0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+
+        Rgba([
+            ((sum[0] + alpha / 2) / alpha) as u8,
+            ((sum[1] + alpha / 2) / alpha) as u8,
+            ((sum[2] + alpha / 2) / alpha) as u8,
+            ((alpha + count / 2) / count) as u8,
+        ])
+    })
+}
+
+impl State {
+    fn map_frames<F>(&mut self, mut func: F)
+    where
+        F: FnMut(&RgbaImage) -> RgbaImage,
+    {
+        for frame in self.frames.iter_mut() {
+            *frame = DynamicImage::ImageRgba8(func(&frame.to_rgba8()));
+        }
+    }
+    /// Moves the image of every direction to `target(direction)`.
+    fn move_directions<F>(&mut self, target: F)
+    where
+        F: Fn(Direction) -> Direction,
+    {
+        let mut frames = self.frames.clone();
+
+        for frame in 0..self.frame_count {
+            for direction in 0..self.dirs {
+                let Some(source) = Direction::from_index(direction) else {
+                    continue;
+                };
+                let target = target(source);
+                if target.index() >= self.dirs {
+                    continue;
+                }
+                frames[(frame * self.dirs + target.index()) as usize] =
+                    self.frames[(frame * self.dirs + direction) as usize].clone();
+            }
+        }
+
+        self.frames = frames;
+    }
+    pub fn shift(&mut self, dir: Direction, offset: i64, wrap: bool) {
+        self.map_frames(|image| shift_image(image, dir, offset, wrap));
+    }
+    pub fn turn(&mut self, angle: f64, mode: GeometryMode) {
+        self.map_frames(|image| turn_image(image, angle));
+
+        if mode == GeometryMode::Byond && self.dirs > 1 && angle % 45. == 0. {
+            let steps = (angle / 45.) as i64;
+            if self.dirs == 8 || steps % 2 == 0 {
+                self.move_directions(|dir| dir.turn(steps));
+            }
+        }
+    }
+    pub fn flip(&mut self, dir: Direction, mode: GeometryMode) -> DmiResult<()> {
+        let horizontal = match dir {
+            Direction::East | Direction::West => true,
+            Direction::North | Direction::South => false,
+            _ => return Err(DmiError::UnsupportedDirection),
+        };
+
+        self.map_frames(|image| {
+            if horizontal {
+                imageops::flip_horizontal(image)
+            } else {
+                imageops::flip_vertical(image)
+            }
+        });
+
+        if mode == GeometryMode::Byond && self.dirs > 1 {
+            self.move_directions(|dir| dir.mirror(horizontal));
+        }
+
+        Ok(())
+    }
+    /// Scales every frame to `width` x `height`, hotspots keep pointing at the same part of the icon.
+    pub fn scale(&mut self, width: u32, height: u32, mode: GeometryMode) {
+        if let Some(frame) = self.frames.first() {
+            let (old_width, old_height) = (frame.width(), frame.height());
+            self.hotspots = self
+                .hotspots
+                .iter()
+                .map(|hotspot| scale_hotspot(hotspot, old_width, old_height, width, height))
+                .collect();
+        }
+
+        self.map_frames(|image| match mode {
+            GeometryMode::Byond => scale_image(image, width, height),
+            GeometryMode::Simple => {
+                imageops::resize(image, width, height, imageops::FilterType::Nearest)
+            }
+        });
+    }
+}
+
+impl Dmi {
+    pub fn shift(&mut self, dir: Direction, offset: i64, wrap: bool, scope: &Scope) {
+        for (index, state) in self.states.iter_mut().enumerate() {
+            if scope.contains_state(index) {
+                state.shift(dir, offset, wrap);
+            }
+        }
+    }
+    pub fn turn(&mut self, angle: f64, mode: GeometryMode, scope: &Scope) {
+        for (index, state) in self.states.iter_mut().enumerate() {
+            if scope.contains_state(index) {
+                state.turn(angle, mode);
+            }
+        }
+    }
+    pub fn flip(&mut self, dir: Direction, mode: GeometryMode, scope: &Scope) -> DmiResult<()> {
+        for (index, state) in self.states.iter_mut().enumerate() {
+            if scope.contains_state(index) {
+                state.flip(dir, mode)?;
+            }
+        }
+        Ok(())
+    }
+    pub fn scale(&mut self, width: u32, height: u32, mode: GeometryMode) {
+        self.width = width;
+        self.height = height;
+        for state in self.states.iter_mut() {
+            state.scale(width, height, mode);
+        }
+    }
+}
@@ -1,8 +1,40 @@
+mod animation;
+mod blend;
+mod color_matrix;
+mod cutter;
 mod dmi;
+mod emissive;
 mod errors;
+mod gags;
+mod geometry;
+mod lint;
 mod lua;
 mod macros;
+mod merge;
+mod palette;
+mod quantize;
+mod recipe;
+mod scalers;
+mod sort;
+mod split;
+mod tiles;
 mod utils;
 
+pub use animation::*;
+pub use blend::*;
+pub use color_matrix::*;
+pub use cutter::*;
 pub use dmi::*;
+pub use emissive::*;
+pub use gags::*;
+pub use geometry::*;
+pub use lint::*;
+pub use merge::*;
+pub use palette::*;
+pub use quantize::*;
+pub use recipe::*;
+pub use scalers::*;
+pub use sort::*;
+pub use split::*;
+pub use tiles::*;
 pub use utils::check_latest_version;
@@ -0,0 +1,432 @@
+//! Checks for DMI files that can run over whole directories, and their fixes.
+
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+use crate::animation::hotspot_parts;
+use crate::dmi::{color_matches, Direction, Dmi, DmiResult, SaveOptions, State};
+use crate::quantize::nearest_color;
+
+/// All `.dmi` files under the directory, sorted by path.
+pub fn dmi_files<P: AsRef<Path>>(dir: P) -> DmiResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for entry in read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(dmi_files(&path)?);
+        } else if path.extension().is_some_and(|extension| extension == "dmi") {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// A visible pixel whose color is not in the allowed palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteViolation {
+    /// Index of the state in the DMI.
+    pub index: usize,
+    pub state: String,
+    pub dir: u32,
+    pub frame: u32,
+    pub x: u32,
+    pub y: u32,
+    pub color: Rgba<u8>,
+}
+
+fn allowed(pixel: Rgba<u8>, palette: &[Rgba<u8>], tolerance: u8) -> bool {
+    pixel[3] == 0
+        || palette
+            .iter()
+            .any(|color| color_matches(pixel, *color, tolerance))
+}
+
+impl State {
+    /// Visible pixels of every frame that do not match a palette color within `tolerance`.
+    /// The `index` of the violations is 0.
+    pub fn palette_violations(&self, palette: &[Rgba<u8>], tolerance: u8) -> Vec<PaletteViolation> {
+        let mut violations = Vec::new();
+
+        for frame in 0..self.frame_count {
+            for dir in 0..self.dirs {
+                let image = self.frames[(frame * self.dirs + dir) as usize].to_rgba8();
+                for (x, y, pixel) in image.enumerate_pixels() {
+                    if !allowed(*pixel, palette, tolerance) {
+                        violations.push(PaletteViolation {
+                            index: 0,
+                            state: self.name.clone(),
+                            dir,
+                            frame,
+                            x,
+                            y,
+                            color: *pixel,
+                        });
+                    }
+                }
+            }
+        }
+
+        violations
+    }
+    /// Replaces the off-palette pixels with the nearest palette color.
+    /// Returns the number of changed pixels.
+    pub fn snap_to_palette(&mut self, palette: &[Rgba<u8>], tolerance: u8) -> usize {
+        if palette.is_empty() {
+            return 0;
+        }
+
+        let mut changed = 0;
+
+        for frame in self.frames.iter_mut() {
+            let mut image = frame.to_rgba8();
+            for pixel in image.pixels_mut() {
+                if !allowed(*pixel, palette, tolerance) {
+                    *pixel = nearest_color(*pixel, palette);
+                    changed += 1;
+                }
+            }
+            *frame = DynamicImage::ImageRgba8(image);
+        }
+
+        changed
+    }
+}
+
+impl Dmi {
+    pub fn palette_violations(&self, palette: &[Rgba<u8>], tolerance: u8) -> Vec<PaletteViolation> {
+        self.states
+            .iter()
+            .enumerate()
+            .flat_map(|(index, state)| {
+                state
+                    .palette_violations(palette, tolerance)
+                    .into_iter()
+                    .map(move |violation| PaletteViolation { index, ..violation })
+            })
+            .collect()
+    }
+    /// Replaces the off-palette pixels of every state with the nearest palette color.
+    /// Returns the number of changed pixels.
+    pub fn snap_to_palette(&mut self, palette: &[Rgba<u8>], tolerance: u8) -> usize {
+        self.states
+            .iter_mut()
+            .map(|state| state.snap_to_palette(palette, tolerance))
+            .sum()
+    }
+}
+
+/// Checks every DMI under the directory, returning the files that have violations.
+/// Files that fail to open are returned with their error and the walk goes on.
+pub fn lint_palette_dir<P: AsRef<Path>>(
+    dir: P,
+    palette: &[Rgba<u8>],
+    tolerance: u8,
+) -> DmiResult<Vec<(PathBuf, DmiResult<Vec<PaletteViolation>>)>> {
+    let mut results = Vec::new();
+
+    for path in dmi_files(dir)? {
+        match Dmi::open(&path) {
+            Ok(dmi) => {
+                let violations = dmi.palette_violations(palette, tolerance);
+                if !violations.is_empty() {
+                    results.push((path, Ok(violations)));
+                }
+            }
+            Err(error) => results.push((path, Err(error))),
+        }
+    }
+
+    Ok(results)
+}
+
+/// Zeroes the RGB of fully transparent pixels, returning how many were changed.
+pub(crate) fn clear_transparent_pixels(image: &mut RgbaImage) -> usize {
+    let mut changed = 0;
+    for pixel in image.pixels_mut() {
+        if pixel[3] == 0 && *pixel != Rgba([0, 0, 0, 0]) {
+            *pixel = Rgba([0, 0, 0, 0]);
+            changed += 1;
+        }
+    }
+    changed
+}
+
+impl State {
+    /// Zeroes the RGB of fully transparent pixels, returning how many were changed.
+    pub fn clear_transparent(&mut self) -> usize {
+        let mut changed = 0;
+        for frame in self.frames.iter_mut() {
+            let mut image = frame.to_rgba8();
+            let frame_changed = clear_transparent_pixels(&mut image);
+            if frame_changed > 0 {
+                *frame = DynamicImage::ImageRgba8(image);
+                changed += frame_changed;
+            }
+        }
+        changed
+    }
+    /// Number of pixels that are neither fully transparent nor fully opaque.
+    pub fn partial_alpha_pixels(&self) -> usize {
+        self.frames
+            .iter()
+            .map(|frame| {
+                frame
+                    .to_rgba8()
+                    .pixels()
+                    .filter(|pixel| pixel[3] > 0 && pixel[3] < 255)
+                    .count()
+            })
+            .sum()
+    }
+    /// Number of fully transparent pixels that still have RGB values.
+    pub fn transparent_rgb_pixels(&self) -> usize {
+        self.frames
+            .iter()
+            .map(|frame| {
+                frame
+                    .to_rgba8()
+                    .pixels()
+                    .filter(|pixel| pixel[3] == 0 && **pixel != Rgba([0, 0, 0, 0]))
+                    .count()
+            })
+            .sum()
+    }
+}
+
+impl Dmi {
+    /// Zeroes the RGB of fully transparent pixels in every state, returning how many were changed.
+    pub fn clear_transparent(&mut self) -> usize {
+        self.states
+            .iter_mut()
+            .map(|state| state.clear_transparent())
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CollapseReport {
+    /// Index of every collapsed state with its old and new `dirs`.
+    pub states: Vec<(usize, u32, u32)>,
+    /// Encoded size of the DMI before and after collapsing.
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+}
+
+impl CollapseReport {
+    pub fn bytes_saved(&self) -> usize {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+}
+
+impl State {
+    /// The image of a direction in a frame with the positions of its hotspots.
+    fn direction_content(&self, frame: u32, dir: u32) -> (RgbaImage, Vec<String>) {
+        let index = frame * self.dirs + dir;
+
+        let mut hotspots = self
+            .hotspots
+            .iter()
+            .filter_map(|hotspot| hotspot_parts(hotspot))
+            .filter(|(_, hotspot_index)| *hotspot_index == index)
+            .map(|(position, _)| position.trim().to_string())
+            .collect::<Vec<_>>();
+        hotspots.sort();
+
+        (self.frames[index as usize].to_rgba8(), hotspots)
+    }
+    fn directions_match(&self, dir: u32, other: u32) -> bool {
+        (0..self.frame_count)
+            .all(|frame| self.direction_content(frame, dir) == self.direction_content(frame, other))
+    }
+    /// The `dirs` the state can be reduced to without changing how it looks. Every
+    /// direction matching south collapses to 1, diagonals matching the cardinal direction
+    /// BYOND falls back to collapse 8 to 4.
+    pub fn collapsible_dirs(&self) -> Option<u32> {
+        if self.dirs > 1 && (1..self.dirs).all(|dir| self.directions_match(dir, 0)) {
+            return Some(1);
+        }
+
+        if self.dirs == 8
+            && Direction::DIAGONALS.iter().all(|direction| {
+                self.directions_match(direction.index(), direction.cardinal_fallback().index())
+            })
+        {
+            return Some(4);
+        }
+
+        None
+    }
+    /// Drops the redundant directions found by `collapsible_dirs`, returning the new `dirs`.
+    pub fn collapse_dirs(&mut self) -> Option<u32> {
+        let dirs = self.collapsible_dirs()?;
+
+        let mut frames = Vec::with_capacity((self.frame_count * dirs) as usize);
+        let mut hotspots = Vec::new();
+
+        for frame in 0..self.frame_count {
+            for dir in 0..dirs {
+                let index = frame * self.dirs + dir;
+                frames.push(self.frames[index as usize].clone());
+
+                for hotspot in self.hotspots.iter() {
+                    if let Some((position, hotspot_index)) = hotspot_parts(hotspot) {
+                        if hotspot_index == index {
+                            hotspots.push(format!("{position},{}", frame * dirs + dir + 1));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.frames = frames;
+        self.dirs = dirs;
+        self.hotspots = hotspots;
+
+        Some(dirs)
+    }
+}
+
+impl Dmi {
+    /// Collapses the redundant directions of every state.
+    pub fn collapse_dirs(&mut self) -> DmiResult<CollapseReport> {
+        let encoded_size = |dmi: &Dmi| -> DmiResult<usize> {
+            let mut bytes = Vec::new();
+            dmi.write(&mut bytes, &SaveOptions::default())?;
+            Ok(bytes.len())
+        };
+
+        let bytes_before = encoded_size(self)?;
+
+        let mut states = Vec::new();
+        for (index, state) in self.states.iter_mut().enumerate() {
+            let dirs = state.dirs;
+            if let Some(collapsed) = state.collapse_dirs() {
+                states.push((index, dirs, collapsed));
+            }
+        }
+
+        let bytes_after = if states.is_empty() {
+            bytes_before
+        } else {
+            encoded_size(self)?
+        };
+
+        Ok(CollapseReport {
+            states,
+            bytes_before,
+            bytes_after,
+        })
+    }
+}
+
+/// Which checks `Dmi::lint` runs, all of them are off by default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LintOptions {
+    /// Allowed palette and the tolerance of each channel.
+    pub palette: Option<(Vec<Rgba<u8>>, u8)>,
+    /// Flags states with pixels that are neither fully transparent nor fully opaque.
+    pub forbid_partial_alpha: bool,
+    /// Flags states with fully transparent pixels that still have RGB values.
+    pub transparent_rgb: bool,
+    /// Flags states with directions that are the same as the ones BYOND would show instead.
+    pub redundant_dirs: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintIssue {
+    OffPalette(PaletteViolation),
+    PartialAlpha {
+        index: usize,
+        state: String,
+        pixels: usize,
+    },
+    TransparentRgb {
+        index: usize,
+        state: String,
+        pixels: usize,
+    },
+    RedundantDirs {
+        index: usize,
+        state: String,
+        dirs: u32,
+        collapsible_to: u32,
+    },
+}
+
+impl Dmi {
+    /// Runs the enabled checks, palette violations come first and the other issues follow
+    /// in state order.
+    pub fn lint(&self, options: &LintOptions) -> Vec<LintIssue> {
+        let mut issues = Vec::new();
+
+        if let Some((palette, tolerance)) = &options.palette {
+            issues.extend(
+                self.palette_violations(palette, *tolerance)
+                    .into_iter()
+                    .map(LintIssue::OffPalette),
+            );
+        }
+
+        for (index, state) in self.states.iter().enumerate() {
+            if options.forbid_partial_alpha {
+                let pixels = state.partial_alpha_pixels();
+                if pixels > 0 {
+                    issues.push(LintIssue::PartialAlpha {
+                        index,
+                        state: state.name.clone(),
+                        pixels,
+                    });
+                }
+            }
+            if options.transparent_rgb {
+                let pixels = state.transparent_rgb_pixels();
+                if pixels > 0 {
+                    issues.push(LintIssue::TransparentRgb {
+                        index,
+                        state: state.name.clone(),
+                        pixels,
+                    });
+                }
+            }
+            if options.redundant_dirs {
+                if let Some(collapsible_to) = state.collapsible_dirs() {
+                    issues.push(LintIssue::RedundantDirs {
+                        index,
+                        state: state.name.clone(),
+                        dirs: state.dirs,
+                        collapsible_to,
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+}
+
+/// Lints every DMI under the directory, returning the files that have issues.
+/// Files that fail to open are returned with their error and the walk goes on.
+pub fn lint_dir<P: AsRef<Path>>(
+    dir: P,
+    options: &LintOptions,
+) -> DmiResult<Vec<(PathBuf, DmiResult<Vec<LintIssue>>)>> {
+    let mut results = Vec::new();
+
+    for path in dmi_files(dir)? {
+        match Dmi::open(&path) {
+            Ok(dmi) => {
+                let issues = dmi.lint(options);
+                if !issues.is_empty() {
+                    results.push((path, Ok(issues)));
+                }
+            }
+            Err(error) => results.push((path, Err(error))),
+        }
+    }
+
+    Ok(results)
+}
@@ -1,15 +1,24 @@
+use image::Rgba;
 use mlua::prelude::*;
 use std::cmp::Ordering;
 use std::fs::{self, read_dir, remove_dir_all};
 use std::path::Path;
 
+use crate::color_matrix::ColorMatrix;
+use crate::cutter::{CutterConfig, JunctionSet};
 use crate::dmi::*;
+use crate::emissive::{EmissiveOptions, EmissiveSelector, DEFAULT_EMISSIVE_THRESHOLD};
 use crate::errors::ExternalError;
-use crate::macros::safe;
+use crate::gags::{open_gags_references, parse_gags_colors, GagsConfig, GagsReferences};
+use crate::macros::{safe, unwind_safe};
+use crate::merge::{ConflictPolicy, MergePolicy, SizePolicy};
+use crate::palette::{open_palette, save_palette};
+use crate::scalers::ResizeMethod;
+use crate::sort::{SortKey, SortOrder};
 use crate::utils::check_latest_version;
 
 #[mlua::lua_module(name = "dmi_module")]
-fn module(lua: &Lua) -> LuaResult<LuaTable> {
+fn module(lua: &Lua) -> LuaResult<LuaTable<'_>> {
     let exports = lua.create_table()?;
 
     exports.set("new_file", lua.create_function(safe!(new_file))?)?;
@@ -21,12 +30,64 @@ fn module(lua: &Lua) -> LuaResult<LuaTable> {
     exports.set("resize", lua.create_function(safe!(resize))?)?;
     exports.set("crop", lua.create_function(safe!(crop))?)?;
     exports.set("expand", lua.create_function(safe!(expand))?)?;
-    exports.set("overlay_color", lua.create_function(overlay_color)?)?;
+    exports.set("resize_canvas", lua.create_function(safe!(resize_canvas))?)?;
+    exports.set("anchor_offset", lua.create_function(safe!(anchor_offset))?)?;
+    exports.set(
+        "generate_directions",
+        lua.create_function(safe!(generate_directions))?,
+    )?;
+    exports.set(
+        "content_bounds",
+        lua.create_function(safe!(content_bounds))?,
+    )?;
+    exports.set("trim", lua.create_function(safe!(trim))?)?;
+    exports.set(
+        "replace_colors",
+        lua.create_function(safe!(replace_colors))?,
+    )?;
+    exports.set("map_colors", lua.create_function(safe!(map_colors))?)?;
+    exports.set(
+        "extract_emissive",
+        lua.create_function(safe!(extract_emissive))?,
+    )?;
+    exports.set("render_gags", lua.create_function(safe!(render_gags))?)?;
+    exports.set("merge", lua.create_function(safe!(merge))?)?;
+    exports.set("sort_states", lua.create_function(safe!(sort_states))?)?;
+    exports.set("palette", lua.create_function(safe!(palette))?)?;
+    exports.set(
+        "export_palette",
+        lua.create_function(safe!(export_palette))?,
+    )?;
+    exports.set(
+        "palette_violations",
+        lua.create_function(safe!(palette_violations))?,
+    )?;
+    exports.set(
+        "snap_to_palette",
+        lua.create_function(safe!(snap_to_palette))?,
+    )?;
+    exports.set("collapse_dirs", lua.create_function(safe!(collapse_dirs))?)?;
+    exports.set("cut_junctions", lua.create_function(safe!(cut_junctions))?)?;
+    exports.set(
+        "missing_junctions",
+        lua.create_function(safe!(missing_junctions))?,
+    )?;
+    exports.set(
+        "apply_color_matrix",
+        lua.create_function(safe!(apply_color_matrix))?,
+    )?;
+    exports.set(
+        "overlay_color",
+        lua.create_function(unwind_safe!(overlay_color))?,
+    )?;
     exports.set("remove_dir", lua.create_function(safe!(remove_dir))?)?;
-    exports.set("exists", lua.create_function(exists)?)?;
-    exports.set("check_update", lua.create_function(check_update)?)?;
+    exports.set("exists", lua.create_function(unwind_safe!(exists))?)?;
+    exports.set(
+        "check_update",
+        lua.create_function(unwind_safe!(check_update))?,
+    )?;
     exports.set("open_repo", lua.create_function(safe!(open_repo))?)?;
-    exports.set("instances", lua.create_function(instances)?)?;
+    exports.set("instances", lua.create_function(unwind_safe!(instances))?)?;
     exports.set("save_dialog", lua.create_function(safe!(save_dialog))?)?;
 
     Ok(exports)
@@ -35,14 +96,14 @@ fn module(lua: &Lua) -> LuaResult<LuaTable> {
 fn new_file(
     lua: &Lua,
     (name, width, height, temp): (String, u32, u32, String),
-) -> LuaResult<LuaTable> {
+) -> LuaResult<LuaTable<'_>> {
     let dmi = Dmi::new(name, width, height).to_serialized(temp, false)?;
     let table = dmi.into_lua_table(lua)?;
 
     Ok(table)
 }
 
-fn open_file(lua: &Lua, (filename, temp): (String, String)) -> LuaResult<LuaTable> {
+fn open_file(lua: &Lua, (filename, temp): (String, String)) -> LuaResult<LuaTable<'_>> {
     if !Path::new(&filename).is_file() {
         Err("File does not exist".to_string()).into_lua_err()?
     }
@@ -53,15 +114,27 @@ fn open_file(lua: &Lua, (filename, temp): (String, String)) -> LuaResult<LuaTabl
     Ok(table)
 }
 
-fn save_file<'lua>(_: &'lua Lua, (dmi, filename): (LuaTable, String)) -> LuaResult<LuaValue<'lua>> {
+fn save_file<'lua>(
+    _: &'lua Lua,
+    (dmi, filename, options): (LuaTable, String, Option<LuaTable>),
+) -> LuaResult<LuaValue<'lua>> {
+    let options = SaveOptions {
+        clear_transparent: match options {
+            Some(options) => options
+                .get::<&str, Option<bool>>("clear_transparent")?
+                .unwrap_or(false),
+            None => false,
+        },
+    };
+
     let dmi = SerializedDmi::from_lua_table(dmi)?;
     let dmi = Dmi::from_serialized(dmi)?;
-    dmi.save(filename)?;
+    dmi.save_with(filename, &options)?;
 
     Ok(LuaValue::Nil)
 }
 
-fn new_state(lua: &Lua, (width, height, temp): (u32, u32, String)) -> LuaResult<LuaTable> {
+fn new_state(lua: &Lua, (width, height, temp): (u32, u32, String)) -> LuaResult<LuaTable<'_>> {
     if !Path::new(&temp).exists() {
         Err("Temp directory does not exist".to_string()).into_lua_err()?
     }
@@ -87,7 +160,7 @@ fn copy_state<'lua>(_: &'lua Lua, (state, temp): (LuaTable, String)) -> LuaResul
     Ok(LuaValue::Nil)
 }
 
-fn paste_state(lua: &Lua, (width, height, temp): (u32, u32, String)) -> LuaResult<LuaTable> {
+fn paste_state(lua: &Lua, (width, height, temp): (u32, u32, String)) -> LuaResult<LuaTable<'_>> {
     if !Path::new(&temp).exists() {
         Err("Temp directory does not exist".to_string()).into_lua_err()?
     }
@@ -108,17 +181,10 @@ fn resize<'lua>(
     let dmi = SerializedDmi::from_lua_table(dmi)?;
 
     let temp = dmi.temp.clone();
-    let method = match method.as_str() {
-        "nearest" => image::imageops::FilterType::Nearest,
-        "triangle" => image::imageops::FilterType::Triangle,
-        "catmullrom" => image::imageops::FilterType::CatmullRom,
-        "gaussian" => image::imageops::FilterType::Gaussian,
-        "lanczos3" => image::imageops::FilterType::Lanczos3,
-        _ => unreachable!(),
-    };
+    let method = method.parse::<ResizeMethod>()?;
 
     let mut dmi = Dmi::from_serialized(dmi)?;
-    dmi.resize(width, height, method);
+    dmi.resize(width, height, method)?;
     dmi.to_serialized(temp, true)?;
 
     Ok(LuaValue::Nil)
@@ -152,6 +218,471 @@ fn expand<'lua>(
     Ok(LuaValue::Nil)
 }
 
+fn resize_canvas(
+    _: &Lua,
+    (dmi, width, height, anchor, x, y): (LuaTable, u32, u32, String, Option<i64>, Option<i64>),
+) -> LuaResult<Vec<Vec<String>>> {
+    let anchor = match anchor.as_str() {
+        "custom" => Anchor::Custom(x.unwrap_or(0), y.unwrap_or(0)),
+        anchor => anchor.parse::<Anchor>()?,
+    };
+
+    let dmi = SerializedDmi::from_lua_table(dmi)?;
+    let temp = dmi.temp.clone();
+
+    let mut dmi = Dmi::from_serialized(dmi)?;
+    dmi.resize_canvas(width, height, anchor);
+    dmi.to_serialized(temp, true)?;
+
+    let hotspots = dmi.states.into_iter().map(|state| state.hotspots).collect();
+
+    Ok(hotspots)
+}
+
+fn anchor_offset(
+    lua: &Lua,
+    (anchor, width, height, new_width, new_height): (String, u32, u32, u32, u32),
+) -> LuaResult<LuaTable<'_>> {
+    let (x, y) = anchor
+        .parse::<Anchor>()?
+        .offset(width, height, new_width, new_height);
+
+    let table = lua.create_table()?;
+    table.set("x", x)?;
+    table.set("y", y)?;
+
+    Ok(table)
+}
+
+fn generate_directions<'lua>(
+    lua: &'lua Lua,
+    (state, temp, preset): (LuaTable, String, String),
+) -> LuaResult<LuaTable<'lua>> {
+    if !Path::new(&temp).exists() {
+        Err("Temp directory does not exist".to_string()).into_lua_err()?
+    }
+
+    let preset = preset.parse::<DirectionPreset>()?;
+
+    let state = SerializedState::from_lua_table(state)?;
+    let mut state = State::from_serialized(state, &temp)?;
+    state.generate_directions(preset)?;
+
+    let state = state.to_serialized(temp)?;
+    let table = state.into_lua_table(lua)?;
+
+    Ok(table)
+}
+
+fn content_bounds<'lua>(
+    lua: &'lua Lua,
+    (dmi, symmetric, scope): (LuaTable, Option<bool>, Option<LuaTable>),
+) -> LuaResult<LuaValue<'lua>> {
+    let dmi = SerializedDmi::from_lua_table(dmi)?;
+    let scope = match scope {
+        Some(scope) => Scope::from_lua_table(scope)?,
+        None => Scope::default(),
+    };
+
+    let dmi = Dmi::from_serialized(dmi)?;
+
+    let Some(mut bounds) = dmi.content_bounds(&scope) else {
+        return Ok(LuaValue::Nil);
+    };
+
+    if symmetric.unwrap_or(false) {
+        bounds = bounds.symmetric_within(dmi.width, dmi.height);
+    }
+
+    Ok(LuaValue::Table(bounds.into_lua_table(lua)?))
+}
+
+fn trim<'lua>(
+    lua: &'lua Lua,
+    (dmi, symmetric, scope): (LuaTable, Option<bool>, Option<LuaTable>),
+) -> LuaResult<LuaValue<'lua>> {
+    let dmi = SerializedDmi::from_lua_table(dmi)?;
+    let temp = dmi.temp.clone();
+    let scope = match scope {
+        Some(scope) => Scope::from_lua_table(scope)?,
+        None => Scope::default(),
+    };
+
+    let mut dmi = Dmi::from_serialized(dmi)?;
+
+    let Some(bounds) = dmi.trim(symmetric.unwrap_or(false), &scope) else {
+        return Ok(LuaValue::Nil);
+    };
+
+    dmi.to_serialized(temp, true)?;
+
+    let table = bounds.into_lua_table(lua)?;
+    let hotspots = dmi
+        .states
+        .into_iter()
+        .map(|state| state.hotspots)
+        .collect::<Vec<_>>();
+    table.set("hotspots", hotspots)?;
+
+    Ok(LuaValue::Table(table))
+}
+
+fn replace_colors(
+    _: &Lua,
+    (dmi, map, tolerance, scope, preview): (
+        LuaTable,
+        Vec<LuaTable>,
+        Option<u8>,
+        Option<LuaTable>,
+        Option<bool>,
+    ),
+) -> LuaResult<Vec<usize>> {
+    let dmi = SerializedDmi::from_lua_table(dmi)?;
+    let temp = dmi.temp.clone();
+
+    let map = map
+        .into_iter()
+        .map(|pair| {
+            let from = color_from_lua(pair.get::<&str, Vec<u8>>("from")?)?;
+            let to = color_from_lua(pair.get::<&str, Vec<u8>>("to")?)?;
+            Ok((from, to))
+        })
+        .collect::<LuaResult<Vec<_>>>()?;
+    let scope = match scope {
+        Some(scope) => Scope::from_lua_table(scope)?,
+        None => Scope::default(),
+    };
+    let preview = preview.unwrap_or(false);
+
+    let mut dmi = Dmi::from_serialized(dmi)?;
+    let counts = dmi.replace_colors(&map, tolerance.unwrap_or(0), &scope, preview);
+
+    if !preview {
+        dmi.to_serialized(temp, true)?;
+    }
+
+    Ok(counts)
+}
+
+fn map_colors<'lua>(
+    lua: &'lua Lua,
+    (state, temp, matrix): (LuaTable, String, Vec<f32>),
+) -> LuaResult<LuaTable<'lua>> {
+    if !Path::new(&temp).exists() {
+        Err("Temp directory does not exist".to_string()).into_lua_err()?
+    }
+
+    let matrix = ColorMatrix::from_values(&matrix)?;
+
+    let state = SerializedState::from_lua_table(state)?;
+    let mut state = State::from_serialized(state, &temp)?;
+    state.map_colors(&matrix);
+
+    let state = state.to_serialized(temp)?;
+    let table = state.into_lua_table(lua)?;
+
+    Ok(table)
+}
+
+fn extract_emissive<'lua>(
+    lua: &'lua Lua,
+    (dmi, index, options): (LuaTable, usize, LuaTable),
+) -> LuaResult<LuaTable<'lua>> {
+    let dmi = SerializedDmi::from_lua_table(dmi)?;
+    let temp = dmi.temp.clone();
+
+    let selector = match options.get::<&str, String>("mode")?.as_str() {
+        "brightness" => EmissiveSelector::Brightness(
+            options
+                .get::<&str, Option<u8>>("threshold")?
+                .unwrap_or(DEFAULT_EMISSIVE_THRESHOLD),
+        ),
+        "colors" => EmissiveSelector::Colors {
+            colors: options
+                .get::<&str, Vec<Vec<u8>>>("colors")?
+                .into_iter()
+                .map(color_from_lua)
+                .collect::<LuaResult<Vec<_>>>()?,
+            tolerance: options.get::<&str, Option<u8>>("tolerance")?.unwrap_or(0),
+        },
+        "mask" => {
+            let mask = SerializedState::from_lua_table(options.get::<&str, LuaTable>("mask")?)?;
+            EmissiveSelector::Mask(State::from_serialized(mask, &temp)?)
+        }
+        mode => Err(format!("Unknown emissive mode \"{mode}\"")).into_lua_err()?,
+    };
+
+    let defaults = EmissiveOptions::default();
+    let color = if options
+        .get::<&str, Option<bool>>("keep_colors")?
+        .unwrap_or(false)
+    {
+        None
+    } else {
+        match options.get::<&str, Option<Vec<u8>>>("color")? {
+            Some(color) => Some(color_from_lua(color)?),
+            None => defaults.color,
+        }
+    };
+    let options = EmissiveOptions {
+        selector,
+        suffix: options
+            .get::<&str, Option<String>>("suffix")?
+            .unwrap_or(defaults.suffix),
+        color,
+    };
+
+    let index = index
+        .checked_sub(1)
+        .ok_or_else(|| "Invalid state index".to_string().into_lua_err())?;
+
+    let mut dmi = Dmi::from_serialized(dmi)?;
+    let index = dmi.extract_emissive(index, &options)?;
+
+    let state = dmi.states[index].to_serialized(temp)?;
+    let table = state.into_lua_table(lua)?;
+
+    Ok(table)
+}
+
+fn merge<'lua>(
+    lua: &'lua Lua,
+    (dmi, filename, conflicts, size, option): (LuaTable, String, String, String, Option<String>),
+) -> LuaResult<LuaTable<'lua>> {
+    if !Path::new(&filename).is_file() {
+        Err("File does not exist".to_string()).into_lua_err()?
+    }
+
+    let size = match size.as_str() {
+        "expand" => SizePolicy::Expand(option.as_deref().unwrap_or("center").parse::<Anchor>()?),
+        "scale" => SizePolicy::Scale(
+            option
+                .as_deref()
+                .unwrap_or("nearest")
+                .parse::<ResizeMethod>()?,
+        ),
+        "reject" => SizePolicy::Reject,
+        size => Err(format!("Unknown size policy \"{size}\"")).into_lua_err()?,
+    };
+    let policy = MergePolicy {
+        conflicts: conflicts.parse::<ConflictPolicy>()?,
+        size,
+    };
+
+    let dmi = SerializedDmi::from_lua_table(dmi)?;
+    let temp = dmi.temp.clone();
+
+    let mut dmi = Dmi::from_serialized(dmi)?;
+    let report = dmi.merge(Dmi::open(filename)?, &policy)?;
+
+    let renamed = lua.create_table()?;
+    for (from, to) in report.renamed {
+        let entry = lua.create_table()?;
+        entry.set("from", from)?;
+        entry.set("to", to)?;
+        renamed.push(entry)?;
+    }
+
+    let table = lua.create_table()?;
+    table.set("dmi", dmi.to_serialized(temp, true)?.into_lua_table(lua)?)?;
+    table.set("added", report.added)?;
+    table.set("replaced", report.replaced)?;
+    table.set("skipped", report.skipped)?;
+    table.set("renamed", renamed)?;
+
+    Ok(table)
+}
+
+/// Returns the 1-based indexes of the states in sorted order, so the editor can reorder
+/// its own state tables.
+fn sort_states(
+    _: &Lua,
+    (dmi, order, movement_last): (LuaTable, String, bool),
+) -> LuaResult<Vec<usize>> {
+    let key = SortKey {
+        order: order.parse::<SortOrder>()?,
+        movement_last,
+    };
+
+    let dmi = SerializedDmi::from_lua_table(dmi)?;
+    let mut indexes = (0..dmi.states.len()).collect::<Vec<_>>();
+    indexes.sort_by(|a, b| {
+        let (a, b) = (&dmi.states[*a], &dmi.states[*b]);
+        key.compare((&a.name, a.movement), (&b.name, b.movement))
+    });
+
+    Ok(indexes.into_iter().map(|index| index + 1).collect())
+}
+
+fn palette<'lua>(lua: &'lua Lua, dmi: LuaTable) -> LuaResult<LuaTable<'lua>> {
+    let dmi = Dmi::from_serialized(SerializedDmi::from_lua_table(dmi)?)?;
+
+    let table = lua.create_table()?;
+    for (color, count) in dmi.palette() {
+        let entry = lua.create_table()?;
+        entry.set("red", color[0])?;
+        entry.set("green", color[1])?;
+        entry.set("blue", color[2])?;
+        entry.set("alpha", color[3])?;
+        entry.set("count", count)?;
+        table.push(entry)?;
+    }
+
+    Ok(table)
+}
+
+fn export_palette<'lua>(
+    _: &'lua Lua,
+    (dmi, filename): (LuaTable, String),
+) -> LuaResult<LuaValue<'lua>> {
+    let dmi = Dmi::from_serialized(SerializedDmi::from_lua_table(dmi)?)?;
+    let colors = dmi
+        .palette()
+        .into_iter()
+        .map(|(color, _)| color)
+        .collect::<Vec<_>>();
+
+    save_palette(filename, &colors)?;
+
+    Ok(LuaValue::Nil)
+}
+
+fn palette_violations<'lua>(
+    lua: &'lua Lua,
+    (dmi, palette, tolerance): (LuaTable, String, Option<u8>),
+) -> LuaResult<LuaTable<'lua>> {
+    let palette = open_palette(palette)?;
+    let dmi = Dmi::from_serialized(SerializedDmi::from_lua_table(dmi)?)?;
+
+    let table = lua.create_table()?;
+    for violation in dmi.palette_violations(&palette, tolerance.unwrap_or(0)) {
+        let entry = lua.create_table()?;
+        entry.set("state", violation.index + 1)?;
+        entry.set("name", violation.state)?;
+        entry.set("dir", violation.dir + 1)?;
+        entry.set("frame", violation.frame + 1)?;
+        entry.set("x", violation.x)?;
+        entry.set("y", violation.y)?;
+        entry.set("red", violation.color[0])?;
+        entry.set("green", violation.color[1])?;
+        entry.set("blue", violation.color[2])?;
+        entry.set("alpha", violation.color[3])?;
+        table.push(entry)?;
+    }
+
+    Ok(table)
+}
+
+fn snap_to_palette<'lua>(
+    lua: &'lua Lua,
+    (dmi, palette, tolerance): (LuaTable, String, Option<u8>),
+) -> LuaResult<LuaTable<'lua>> {
+    let palette = open_palette(palette)?;
+
+    let dmi = SerializedDmi::from_lua_table(dmi)?;
+    let temp = dmi.temp.clone();
+
+    let mut dmi = Dmi::from_serialized(dmi)?;
+    dmi.snap_to_palette(&palette, tolerance.unwrap_or(0));
+
+    let dmi = dmi.to_serialized(temp, true)?;
+    let table = dmi.into_lua_table(lua)?;
+
+    Ok(table)
+}
+
+fn collapse_dirs<'lua>(lua: &'lua Lua, dmi: LuaTable) -> LuaResult<LuaTable<'lua>> {
+    let dmi = SerializedDmi::from_lua_table(dmi)?;
+    let temp = dmi.temp.clone();
+
+    let mut dmi = Dmi::from_serialized(dmi)?;
+    let report = dmi.collapse_dirs()?;
+
+    let table = lua.create_table()?;
+    table.set("states", report.states.len())?;
+    table.set("bytes_saved", report.bytes_saved())?;
+    if !report.states.is_empty() {
+        table.set("dmi", dmi.to_serialized(temp, true)?.into_lua_table(lua)?)?;
+    }
+
+    Ok(table)
+}
+
+fn render_gags(
+    lua: &Lua,
+    (config, icon, colors, temp, references): (String, String, String, String, Option<String>),
+) -> LuaResult<LuaTable<'_>> {
+    if !Path::new(&temp).exists() {
+        Err("Temp directory does not exist".to_string()).into_lua_err()?
+    }
+
+    let colors = parse_gags_colors(&colors)?;
+    let references = match references {
+        Some(references) => open_gags_references(references)?,
+        None => GagsReferences::new(),
+    };
+    let dmi = GagsConfig::open(config, icon)?.render(&colors, &references)?;
+    let dmi = dmi.to_serialized(temp, false)?;
+    let table = dmi.into_lua_table(lua)?;
+
+    Ok(table)
+}
+
+fn cut_junctions<'lua>(
+    lua: &'lua Lua,
+    (dmi, prefix, naming, set, temp): (LuaTable, String, String, String, String),
+) -> LuaResult<LuaTable<'lua>> {
+    let config = CutterConfig {
+        prefix,
+        naming,
+        set: set.parse::<JunctionSet>()?,
+    };
+
+    let dmi = SerializedDmi::from_lua_table(dmi)?;
+    let dmi = Dmi::from_serialized(dmi)?.cut_junctions(&config)?;
+    let dmi = dmi.to_serialized(temp, false)?;
+    let table = dmi.into_lua_table(lua)?;
+
+    Ok(table)
+}
+
+fn missing_junctions(
+    _: &Lua,
+    (dmi, prefix, naming, set): (LuaTable, String, String, String),
+) -> LuaResult<Vec<u8>> {
+    let config = CutterConfig {
+        prefix,
+        naming,
+        set: set.parse::<JunctionSet>()?,
+    };
+
+    let dmi = SerializedDmi::from_lua_table(dmi)?;
+    let dmi = Dmi::from_serialized(dmi)?;
+
+    Ok(dmi.missing_junctions(&config))
+}
+
+fn apply_color_matrix<'lua>(
+    lua: &'lua Lua,
+    (matrix, width, height, bytes): (Vec<f32>, u32, u32, LuaString<'lua>),
+) -> LuaResult<LuaString<'lua>> {
+    use image::{EncodableLayout, ImageBuffer};
+
+    let matrix = ColorMatrix::from_values(&matrix)?;
+
+    let Some(mut image) =
+        ImageBuffer::<Rgba<u8>, _>::from_vec(width, height, bytes.as_bytes().to_vec())
+    else {
+        return Err("Bytes do not match the image size".to_string()).into_lua_err();
+    };
+
+    for pixel in image.pixels_mut() {
+        *pixel = matrix.apply_pixel(*pixel);
+    }
+
+    lua.create_string(image.as_bytes())
+}
+
 fn overlay_color<'lua>(
     _: &'lua Lua,
     (r, g, b, width, height, bytes): (u8, u8, u8, u32, u32, LuaMultiValue<'lua>),
@@ -181,7 +712,7 @@ fn overlay_color<'lua>(
     Ok(LuaMultiValue::from_vec(vec![LuaValue::Nil]))
 }
 
-fn remove_dir(_: &Lua, (path, soft): (String, bool)) -> LuaResult<LuaValue> {
+fn remove_dir(_: &Lua, (path, soft): (String, bool)) -> LuaResult<LuaValue<'_>> {
     let path = Path::new(&path);
 
     if path.is_dir() {
@@ -239,7 +770,7 @@ fn check_update(_: &Lua, (): ()) -> LuaResult<bool> {
     Ok(false)
 }
 
-fn open_repo(_: &Lua, path: Option<String>) -> LuaResult<LuaValue> {
+fn open_repo(_: &Lua, path: Option<String>) -> LuaResult<LuaValue<'_>> {
     let url = if let Some(path) = path {
         format!("{}/{}", env!("CARGO_PKG_REPOSITORY"), path)
     } else {
@@ -253,8 +784,16 @@ fn open_repo(_: &Lua, path: Option<String>) -> LuaResult<LuaValue> {
     Ok(LuaValue::Nil)
 }
 
+fn color_from_lua(color: Vec<u8>) -> LuaResult<Rgba<u8>> {
+    match color[..] {
+        [r, g, b] => Ok(Rgba([r, g, b, 255])),
+        [r, g, b, a] => Ok(Rgba([r, g, b, a])),
+        _ => Err("Colors must have 3 or 4 channels".to_string()).into_lua_err(),
+    }
+}
+
 trait IntoLuaTable {
-    fn into_lua_table(self, lua: &Lua) -> LuaResult<LuaTable>;
+    fn into_lua_table(self, lua: &Lua) -> LuaResult<LuaTable<'_>>;
 }
 
 trait FromLuaTable {
@@ -263,7 +802,7 @@ trait FromLuaTable {
 }
 
 impl IntoLuaTable for SerializedState {
-    fn into_lua_table(self, lua: &Lua) -> LuaResult<LuaTable> {
+    fn into_lua_table(self, lua: &Lua) -> LuaResult<LuaTable<'_>> {
         let table = lua.create_table()?;
 
         table.set("name", self.name)?;
@@ -281,7 +820,7 @@ impl IntoLuaTable for SerializedState {
 }
 
 impl IntoLuaTable for SerializedDmi {
-    fn into_lua_table(self, lua: &Lua) -> LuaResult<LuaTable> {
+    fn into_lua_table(self, lua: &Lua) -> LuaResult<LuaTable<'_>> {
         let table = lua.create_table()?;
         let mut states = Vec::new();
 
@@ -300,6 +839,19 @@ impl IntoLuaTable for SerializedDmi {
     }
 }
 
+impl IntoLuaTable for Rect {
+    fn into_lua_table(self, lua: &Lua) -> LuaResult<LuaTable<'_>> {
+        let table = lua.create_table()?;
+
+        table.set("x", self.x)?;
+        table.set("y", self.y)?;
+        table.set("width", self.width)?;
+        table.set("height", self.height)?;
+
+        Ok(table)
+    }
+}
+
 impl FromLuaTable for SerializedState {
     type Result = SerializedState;
     fn from_lua_table(table: LuaTable) -> LuaResult<Self::Result> {
@@ -351,3 +903,59 @@ impl FromLuaTable for SerializedDmi {
         })
     }
 }
+
+impl FromLuaTable for Scope {
+    type Result = Scope;
+    fn from_lua_table(table: LuaTable) -> LuaResult<Self::Result> {
+        let states = table.get::<&str, Option<Vec<usize>>>("states")?;
+        let frames = table.get::<&str, Option<Vec<u32>>>("frames")?;
+        let dirs = table.get::<&str, Option<Vec<u32>>>("dirs")?;
+
+        let dirs = match dirs {
+            Some(dirs) => Some(
+                dirs.into_iter()
+                    .map(|dir| {
+                        dir.checked_sub(1)
+                            .and_then(Direction::from_index)
+                            .ok_or_else(|| "Invalid direction".to_string().into_lua_err())
+                    })
+                    .collect::<LuaResult<Vec<_>>>()?,
+            ),
+            None => None,
+        };
+
+        let states = match states {
+            Some(states) => Some(
+                states
+                    .into_iter()
+                    .map(|state| {
+                        state
+                            .checked_sub(1)
+                            .ok_or_else(|| "Invalid state index".to_string().into_lua_err())
+                    })
+                    .collect::<LuaResult<Vec<_>>>()?,
+            ),
+            None => None,
+        };
+
+        let frames = match frames {
+            Some(frames) => Some(
+                frames
+                    .into_iter()
+                    .map(|frame| {
+                        frame
+                            .checked_sub(1)
+                            .ok_or_else(|| "Invalid frame index".to_string().into_lua_err())
+                    })
+                    .collect::<LuaResult<Vec<_>>>()?,
+            ),
+            None => None,
+        };
+
+        Ok(Scope {
+            states,
+            frames,
+            dirs,
+        })
+    }
+}
@@ -1,4 +1,6 @@
 use mlua::prelude::*;
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 
 pub fn safe_lua_function<'lua, A, R, F>(
     lua: &'lua Lua,
@@ -10,16 +12,53 @@ where
     R: IntoLuaMulti<'lua>,
     F: Fn(&'lua Lua, A) -> LuaResult<R>,
 {
-    match func(lua, multi) {
-        Ok(r) => Ok((Some(r), None)),
-        Err(err) => Ok((None, Some(err.to_string()))),
+    match catch_unwind(AssertUnwindSafe(|| func(lua, multi))) {
+        Ok(Ok(r)) => Ok((Some(r), None)),
+        Ok(Err(err)) => Ok((None, Some(err.to_string()))),
+        Err(payload) => Ok((None, Some(panic_message(payload)))),
     }
 }
 
+pub fn unwind_safe_lua_function<'lua, A, R, F>(
+    lua: &'lua Lua,
+    func: F,
+    multi: A,
+) -> LuaResult<LuaMultiValue<'lua>>
+where
+    A: FromLuaMulti<'lua>,
+    R: IntoLuaMulti<'lua>,
+    F: Fn(&'lua Lua, A) -> LuaResult<R>,
+{
+    match catch_unwind(AssertUnwindSafe(|| func(lua, multi))) {
+        Ok(result) => result?.into_lua_multi(lua),
+        Err(payload) => (LuaValue::Nil, panic_message(payload)).into_lua_multi(lua),
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    };
+
+    format!("Internal error: {message}")
+}
+
 macro_rules! safe {
     ($func:ident) => {
         |lua, args| $crate::macros::safe_lua_function(lua, $func, args)
     };
 }
 
+/// Like `safe!` but keeps the return values of the function, only panics become `nil, error`.
+macro_rules! unwind_safe {
+    ($func:ident) => {
+        |lua, args| $crate::macros::unwind_safe_lua_function(lua, $func, args)
+    };
+}
+
 pub(crate) use safe;
+pub(crate) use unwind_safe;
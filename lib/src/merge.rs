@@ -0,0 +1,177 @@
+//! Combining the states of two DMIs.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::dmi::{Anchor, Dmi, DmiError, DmiResult};
+use crate::scalers::ResizeMethod;
+
+/// What happens to a state of the other DMI when a state with the same name and
+/// movement flag already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Imports it under a free name, movement pairs are renamed together.
+    Rename,
+    /// Replaces the existing state in place.
+    Replace,
+    /// Keeps the existing state.
+    Skip,
+    /// Fails without changing anything.
+    Error,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = DmiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rename" => Ok(Self::Rename),
+            "replace" => Ok(Self::Replace),
+            "skip" => Ok(Self::Skip),
+            "error" => Ok(Self::Error),
+            _ => Err(DmiError::UnknownConflictPolicy(s.to_string())),
+        }
+    }
+}
+
+/// What happens when the icon sizes of the DMIs differ.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizePolicy {
+    /// Both DMIs are expanded around the anchor to the larger width and height.
+    Expand(Anchor),
+    /// The other DMI is scaled to the size of this one.
+    Scale(ResizeMethod),
+    /// Fails without changing anything.
+    Reject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MergePolicy {
+    pub conflicts: ConflictPolicy,
+    pub size: SizePolicy,
+}
+
+impl Default for MergePolicy {
+    fn default() -> Self {
+        Self {
+            conflicts: ConflictPolicy::Rename,
+            size: SizePolicy::Reject,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    pub added: Vec<String>,
+    pub replaced: Vec<String>,
+    pub skipped: Vec<String>,
+    /// Old and new names of the renamed states.
+    pub renamed: Vec<(String, String)>,
+}
+
+impl Dmi {
+    /// Adds the states of `other` to the end of this DMI.
+    pub fn merge(&mut self, mut other: Dmi, policy: &MergePolicy) -> DmiResult<MergeReport> {
+        let existing = self
+            .states
+            .iter()
+            .map(|state| (state.name.clone(), state.movement))
+            .collect::<HashSet<_>>();
+        let conflicts =
+            |name: &str, movement: bool| existing.contains(&(name.to_string(), movement));
+
+        if policy.conflicts == ConflictPolicy::Error {
+            if let Some(state) = other
+                .states
+                .iter()
+                .find(|state| conflicts(&state.name, state.movement))
+            {
+                return Err(DmiError::StateConflict(state.name.clone()));
+            }
+        }
+
+        if (other.width, other.height) != (self.width, self.height) {
+            match policy.size {
+                SizePolicy::Expand(anchor) => {
+                    let width = self.width.max(other.width);
+                    let height = self.height.max(other.height);
+                    if (self.width, self.height) != (width, height) {
+                        self.resize_canvas(width, height, anchor);
+                    }
+                    if (other.width, other.height) != (width, height) {
+                        other.resize_canvas(width, height, anchor);
+                    }
+                }
+                SizePolicy::Scale(method) => other.resize(self.width, self.height, method)?,
+                SizePolicy::Reject => {
+                    return Err(DmiError::DmiSizeMismatch {
+                        width: self.width,
+                        height: self.height,
+                        other_width: other.width,
+                        other_height: other.height,
+                    })
+                }
+            }
+        }
+
+        let mut report = MergeReport::default();
+
+        if policy.conflicts == ConflictPolicy::Rename {
+            let mut taken = self
+                .states
+                .iter()
+                .chain(other.states.iter())
+                .map(|state| state.name.clone())
+                .collect::<HashSet<_>>();
+
+            let conflicting = other
+                .states
+                .iter()
+                .filter(|state| conflicts(&state.name, state.movement))
+                .map(|state| state.name.clone())
+                .collect::<Vec<_>>();
+
+            for name in conflicting {
+                if report.renamed.iter().any(|(old, _)| *old == name) {
+                    continue;
+                }
+
+                let new_name = (2..)
+                    .map(|index| format!("{name} {index}"))
+                    .find(|candidate| !taken.contains(candidate))
+                    .unwrap();
+                taken.insert(new_name.clone());
+
+                // Every state with the name is renamed so movement states stay paired.
+                for state in other.states.iter_mut().filter(|state| state.name == name) {
+                    state.name = new_name.clone();
+                }
+
+                report.renamed.push((name, new_name));
+            }
+        }
+
+        // Only states that were here before the merge count as conflicts.
+        let original = self.states.len();
+
+        for state in other.states {
+            let existing = self.states[..original].iter().position(|existing| {
+                existing.name == state.name && existing.movement == state.movement
+            });
+
+            match (existing, policy.conflicts) {
+                (Some(index), ConflictPolicy::Replace) => {
+                    report.replaced.push(state.name.clone());
+                    self.states[index] = state;
+                }
+                (Some(_), _) => report.skipped.push(state.name),
+                (None, _) => {
+                    report.added.push(state.name.clone());
+                    self.states.push(state);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
@@ -0,0 +1,240 @@
+//! Palette extraction and GIMP `.gpl`, JASC `.pal` and Adobe `.act` palette files.
+
+use image::{DynamicImage, Rgba};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::dmi::{Dmi, DmiError, DmiResult, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteFormat {
+    /// GIMP palette, alpha is kept with an RGBA channels header as written by Aseprite.
+    Gpl,
+    /// JASC palette, RGB only.
+    Pal,
+    /// Adobe color table, RGB only and at most 256 colors.
+    Act,
+}
+
+impl FromStr for PaletteFormat {
+    type Err = DmiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gpl" => Ok(Self::Gpl),
+            "pal" => Ok(Self::Pal),
+            "act" => Ok(Self::Act),
+            _ => Err(DmiError::UnknownPaletteFormat(s.to_string())),
+        }
+    }
+}
+
+impl PaletteFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> DmiResult<Self> {
+        let extension = path.as_ref().extension().unwrap_or_default();
+        extension.to_string_lossy().parse()
+    }
+}
+
+/// Visible colors of the frames and how many pixels use them, most used first.
+fn count_colors<'a>(frames: impl Iterator<Item = &'a DynamicImage>) -> Vec<(Rgba<u8>, usize)> {
+    let mut counts = HashMap::new();
+    for frame in frames {
+        for pixel in frame.to_rgba8().pixels() {
+            if pixel[3] > 0 {
+                *counts.entry(*pixel).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut palette = counts.into_iter().collect::<Vec<_>>();
+    palette.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.0.cmp(&b.0)));
+    palette
+}
+
+impl State {
+    /// Distinct visible colors with their usage counts, most used first.
+    pub fn palette(&self) -> Vec<(Rgba<u8>, usize)> {
+        count_colors(self.frames.iter())
+    }
+}
+
+impl Dmi {
+    /// Distinct visible colors of all states with their usage counts, most used first.
+    pub fn palette(&self) -> Vec<(Rgba<u8>, usize)> {
+        count_colors(self.states.iter().flat_map(|state| state.frames.iter()))
+    }
+}
+
+pub fn encode_palette(
+    colors: &[Rgba<u8>],
+    format: PaletteFormat,
+    name: &str,
+) -> DmiResult<Vec<u8>> {
+    match format {
+        PaletteFormat::Gpl => {
+            let alpha = colors.iter().any(|color| color[3] != 255);
+
+            let mut text = format!("GIMP Palette\nName: {name}\n");
+            if alpha {
+                text.push_str("Channels: RGBA\n");
+            }
+            text.push_str("#\n");
+
+            for color in colors {
+                text.push_str(&format!("{:3} {:3} {:3}", color[0], color[1], color[2]));
+                if alpha {
+                    text.push_str(&format!(" {:3}", color[3]));
+                }
+                text.push_str(&format!(
+                    "\t#{:02x}{:02x}{:02x}\n",
+                    color[0], color[1], color[2]
+                ));
+            }
+
+            Ok(text.into_bytes())
+        }
+        PaletteFormat::Pal => {
+            let mut text = format!("JASC-PAL\r\n0100\r\n{}\r\n", colors.len());
+            for color in colors {
+                text.push_str(&format!("{} {} {}\r\n", color[0], color[1], color[2]));
+            }
+            Ok(text.into_bytes())
+        }
+        PaletteFormat::Act => {
+            if colors.len() > 256 {
+                return Err(DmiError::PaletteTooLarge(colors.len()));
+            }
+
+            let mut bytes = vec![0; 256 * 3];
+            for (index, color) in colors.iter().enumerate() {
+                bytes[index * 3..index * 3 + 3].copy_from_slice(&color.0[..3]);
+            }
+            // Color count and no transparent index.
+            bytes.extend_from_slice(&(colors.len() as u16).to_be_bytes());
+            bytes.extend_from_slice(&u16::MAX.to_be_bytes());
+
+            Ok(bytes)
+        }
+    }
+}
+
+pub fn decode_palette(bytes: &[u8], format: PaletteFormat) -> DmiResult<Vec<Rgba<u8>>> {
+    let invalid = |reason: &str| DmiError::InvalidPalette(reason.to_string());
+
+    match format {
+        PaletteFormat::Gpl => {
+            let text = String::from_utf8_lossy(bytes);
+            let mut lines = text.lines();
+
+            if lines.next().map(str::trim) != Some("GIMP Palette") {
+                return Err(invalid("missing GIMP Palette header"));
+            }
+
+            let mut alpha = false;
+            let mut colors = Vec::new();
+
+            for line in lines {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                if let Some((key, value)) = line.split_once(':') {
+                    if key == "Channels" {
+                        alpha = value.trim() == "RGBA";
+                    }
+                    if ["Name", "Columns", "Channels"].contains(&key) {
+                        continue;
+                    }
+                }
+
+                let channels = line
+                    .split_whitespace()
+                    .take(if alpha { 4 } else { 3 })
+                    .map(str::parse::<u8>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| invalid(line))?;
+
+                match channels[..] {
+                    [r, g, b] => colors.push(Rgba([r, g, b, 255])),
+                    [r, g, b, a] => colors.push(Rgba([r, g, b, a])),
+                    _ => return Err(invalid(line)),
+                }
+            }
+
+            Ok(colors)
+        }
+        PaletteFormat::Pal => {
+            let text = String::from_utf8_lossy(bytes);
+            let mut lines = text.lines().map(str::trim);
+
+            if lines.next() != Some("JASC-PAL") {
+                return Err(invalid("missing JASC-PAL header"));
+            }
+            lines.next();
+            let count = lines
+                .next()
+                .and_then(|count| count.parse::<usize>().ok())
+                .ok_or_else(|| invalid("missing color count"))?;
+
+            let colors = lines
+                .filter(|line| !line.is_empty())
+                .take(count)
+                .map(|line| {
+                    let channels = line
+                        .split_whitespace()
+                        .map(str::parse::<u8>)
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| invalid(line))?;
+                    match channels[..] {
+                        [r, g, b] => Ok(Rgba([r, g, b, 255])),
+                        _ => Err(invalid(line)),
+                    }
+                })
+                .collect::<DmiResult<Vec<_>>>()?;
+
+            if colors.len() != count {
+                return Err(invalid("fewer colors than the color count"));
+            }
+
+            Ok(colors)
+        }
+        PaletteFormat::Act => {
+            if bytes.len() < 256 * 3 {
+                return Err(invalid("color table is shorter than 768 bytes"));
+            }
+
+            let count = match bytes.get(768..770) {
+                Some(count) => (u16::from_be_bytes([count[0], count[1]]) as usize).min(256),
+                None => 256,
+            };
+
+            Ok(bytes[..count * 3]
+                .chunks_exact(3)
+                .map(|color| Rgba([color[0], color[1], color[2], 255]))
+                .collect())
+        }
+    }
+}
+
+/// Writes the colors in the format of the file extension.
+pub fn save_palette<P: AsRef<Path>>(path: P, colors: &[Rgba<u8>]) -> DmiResult<()> {
+    let format = PaletteFormat::from_path(&path)?;
+    let name = path
+        .as_ref()
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    fs::write(&path, encode_palette(colors, format, &name)?)?;
+
+    Ok(())
+}
+
+/// Reads the colors in the format of the file extension.
+pub fn open_palette<P: AsRef<Path>>(path: P) -> DmiResult<Vec<Rgba<u8>>> {
+    let format = PaletteFormat::from_path(&path)?;
+    decode_palette(&fs::read(path)?, format)
+}
@@ -0,0 +1,288 @@
+//! Reducing the number of colors of states to a target count or a fixed palette.
+
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::collections::{HashMap, HashSet};
+
+use crate::dmi::{Dmi, DmiError, DmiResult, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantizeMethod {
+    MedianCut,
+    /// Refines the median cut palette with the given number of k-means iterations.
+    KMeans(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuantizeTarget {
+    /// Builds a palette with at most this many colors.
+    Colors(usize),
+    /// Maps every pixel to the nearest color of the palette.
+    Palette(Vec<Rgba<u8>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizeOptions {
+    pub target: QuantizeTarget,
+    pub method: QuantizeMethod,
+    /// Ordered dithering, off by default as it is rarely wanted for pixel art.
+    pub dither: bool,
+}
+
+impl Default for QuantizeOptions {
+    fn default() -> Self {
+        Self {
+            target: QuantizeTarget::Colors(32),
+            method: QuantizeMethod::MedianCut,
+            dither: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuantizeReport {
+    /// Visible colors before the reduction, fully transparent pixels are not counted.
+    pub colors_before: usize,
+    pub colors_after: usize,
+    pub palette: Vec<Rgba<u8>>,
+}
+
+/// Squared distance of the colors with the RGB channels weighted by their alpha, so
+/// barely visible pixels match by alpha more than by color.
+pub fn color_distance(a: Rgba<u8>, b: Rgba<u8>) -> u32 {
+    let (a_alpha, b_alpha) = (a[3] as i32, b[3] as i32);
+    let channel = |index: usize| (a[index] as i32 * a_alpha - b[index] as i32 * b_alpha) / 255;
+
+    [channel(0), channel(1), channel(2), a_alpha - b_alpha]
+        .iter()
+        .map(|difference| (difference * difference) as u32)
+        .sum()
+}
+
+pub fn nearest_color(color: Rgba<u8>, palette: &[Rgba<u8>]) -> Rgba<u8> {
+    palette
+        .iter()
+        .min_by_key(|candidate| color_distance(color, **candidate))
+        .copied()
+        .unwrap_or(color)
+}
+
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+/// How far ordered dithering pushes the RGB channels before the nearest color is picked.
+const DITHER_SPREAD: f32 = 32.;
+
+type Histogram = HashMap<Rgba<u8>, u32>;
+
+fn histogram<'a>(frames: impl Iterator<Item = &'a DynamicImage>) -> Histogram {
+    let mut histogram = Histogram::new();
+    for frame in frames {
+        for pixel in frame.to_rgba8().pixels() {
+            if pixel[3] > 0 {
+                *histogram.entry(*pixel).or_default() += 1;
+            }
+        }
+    }
+    histogram
+}
+
+fn weighted_mean(colors: &[(Rgba<u8>, u32)]) -> Rgba<u8> {
+    let total = colors
+        .iter()
+        .map(|(_, count)| *count as u64)
+        .sum::<u64>()
+        .max(1);
+    let channel = |index: usize| {
+        let sum = colors
+            .iter()
+            .map(|(color, count)| color[index] as u64 * *count as u64)
+            .sum::<u64>();
+        ((sum + total / 2) / total) as u8
+    };
+    Rgba([channel(0), channel(1), channel(2), channel(3)])
+}
+
+fn channel_range(colors: &[(Rgba<u8>, u32)], channel: usize) -> u8 {
+    let values = colors.iter().map(|(color, _)| color[channel]);
+    values.clone().max().unwrap_or(0) - values.min().unwrap_or(0)
+}
+
+fn median_cut(histogram: &Histogram, count: usize) -> Vec<Rgba<u8>> {
+    let mut colors = histogram
+        .iter()
+        .map(|(color, count)| (*color, *count))
+        .collect::<Vec<_>>();
+    // Hash map order is random, sorting keeps the result the same between runs.
+    colors.sort_by_key(|(color, _)| color.0);
+
+    let mut boxes = vec![colors];
+
+    while boxes.len() < count {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(index, colors)| {
+                let (channel, range) = (0..4)
+                    .map(|channel| (channel, channel_range(colors, channel)))
+                    .max_by_key(|(_, range)| *range)
+                    .unwrap();
+                (index, channel, range)
+            })
+            .max_by_key(|(_, _, range)| *range);
+
+        let Some((index, channel, _)) = widest else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_by_key(|(color, _)| color[channel]);
+
+        // Split at the weighted median, leaving at least one color on each side.
+        let total = colors.iter().map(|(_, count)| *count as u64).sum::<u64>();
+        let mut seen = 0;
+        let mut split = colors.len() - 1;
+        for (position, (_, count)) in colors.iter().enumerate() {
+            seen += *count as u64;
+            if seen * 2 >= total {
+                split = position + 1;
+                break;
+            }
+        }
+        let split = split.clamp(1, colors.len() - 1);
+
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|colors| weighted_mean(colors)).collect()
+}
+
+fn k_means(histogram: &Histogram, mut palette: Vec<Rgba<u8>>, iterations: u32) -> Vec<Rgba<u8>> {
+    for _ in 0..iterations {
+        let mut clusters = vec![Vec::new(); palette.len()];
+        for (color, count) in histogram.iter() {
+            let (index, _) = palette
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, candidate)| color_distance(*color, **candidate))
+                .unwrap();
+            clusters[index].push((*color, *count));
+        }
+
+        let refined = clusters
+            .iter()
+            .zip(palette.iter())
+            .map(|(cluster, color)| {
+                if cluster.is_empty() {
+                    *color
+                } else {
+                    weighted_mean(cluster)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if refined == palette {
+            break;
+        }
+        palette = refined;
+    }
+
+    palette
+}
+
+fn build_palette(histogram: &Histogram, options: &QuantizeOptions) -> DmiResult<Vec<Rgba<u8>>> {
+    let count = match &options.target {
+        QuantizeTarget::Palette(palette) if palette.is_empty() => {
+            return Err(DmiError::EmptyPalette)
+        }
+        QuantizeTarget::Palette(palette) => return Ok(palette.clone()),
+        QuantizeTarget::Colors(0) => return Err(DmiError::EmptyPalette),
+        QuantizeTarget::Colors(count) => *count,
+    };
+
+    if histogram.len() <= count {
+        let mut palette = histogram.keys().copied().collect::<Vec<_>>();
+        palette.sort_by_key(|color| color.0);
+        return Ok(palette);
+    }
+
+    let palette = median_cut(histogram, count);
+
+    Ok(match options.method {
+        QuantizeMethod::MedianCut => palette,
+        QuantizeMethod::KMeans(iterations) => k_means(histogram, palette, iterations),
+    })
+}
+
+fn remap(frame: &DynamicImage, palette: &[Rgba<u8>], dither: bool) -> DynamicImage {
+    let source = frame.to_rgba8();
+    let mut cache = HashMap::new();
+
+    let image = RgbaImage::from_fn(source.width(), source.height(), |x, y| {
+        let pixel = *source.get_pixel(x, y);
+        if pixel[3] == 0 {
+            return pixel;
+        }
+
+        if dither {
+            let offset = (BAYER[(y % 4) as usize][(x % 4) as usize] as f32 + 0.5) / 16. - 0.5;
+            let channel =
+                |index: usize| (pixel[index] as f32 + offset * DITHER_SPREAD).clamp(0., 255.) as u8;
+            nearest_color(
+                Rgba([channel(0), channel(1), channel(2), pixel[3]]),
+                palette,
+            )
+        } else {
+            *cache
+                .entry(pixel)
+                .or_insert_with(|| nearest_color(pixel, palette))
+        }
+    });
+
+    DynamicImage::ImageRgba8(image)
+}
+
+fn quantize_frames(
+    frames: Vec<&mut DynamicImage>,
+    options: &QuantizeOptions,
+) -> DmiResult<QuantizeReport> {
+    let before = histogram(frames.iter().map(|frame| &**frame));
+    let palette = build_palette(&before, options)?;
+
+    let mut after = HashSet::new();
+    for frame in frames {
+        *frame = remap(frame, &palette, options.dither);
+        after.extend(
+            frame
+                .to_rgba8()
+                .pixels()
+                .filter(|pixel| pixel[3] > 0)
+                .copied(),
+        );
+    }
+
+    Ok(QuantizeReport {
+        colors_before: before.len(),
+        colors_after: after.len(),
+        palette,
+    })
+}
+
+impl State {
+    pub fn quantize(&mut self, options: &QuantizeOptions) -> DmiResult<QuantizeReport> {
+        quantize_frames(self.frames.iter_mut().collect(), options)
+    }
+}
+
+impl Dmi {
+    /// Reduces the colors of all states with one shared palette.
+    pub fn quantize(&mut self, options: &QuantizeOptions) -> DmiResult<QuantizeReport> {
+        quantize_frames(
+            self.states
+                .iter_mut()
+                .flat_map(|state| state.frames.iter_mut())
+                .collect(),
+            options,
+        )
+    }
+}
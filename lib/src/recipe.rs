@@ -0,0 +1,384 @@
+//! Declarative JSON recipes describing how to build a DMI from other icons.
+//!
+//! ```json
+//! {
+//!     "name": "jumpsuit",
+//!     "width": 32,
+//!     "height": 32,
+//!     "inputs": {
+//!         "base": { "dmi": "base.dmi", "state": "jumpsuit" },
+//!         "stripes": { "png": "stripes.png" },
+//!         "red": { "matrix": [1, 0, 0, 0, 0, 0, 0, 0, 0] }
+//!     },
+//!     "states": [
+//!         {
+//!             "name": "jumpsuit_red",
+//!             "from": "base",
+//!             "steps": [
+//!                 { "op": "color_matrix", "input": "red" },
+//!                 { "op": "blend", "input": "stripes", "mode": "overlay" },
+//!                 { "op": "set", "movement": true }
+//!             ]
+//!         }
+//!     ]
+//! }
+//! ```
+//!
+//! Paths are relative to the directory of the recipe.
+
+use image::io::Reader as ImageReader;
+use image::Rgba;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use crate::blend::BlendMode;
+use crate::color_matrix::ColorMatrix;
+use crate::dmi::{Direction, Dmi, DmiError, DmiResult, Scope, State};
+use crate::geometry::GeometryMode;
+use crate::scalers::ResizeMethod;
+
+#[derive(Deserialize, Debug)]
+pub struct Recipe {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// Inputs are loaded in name order, so the first bad input reported is always the same.
+    #[serde(default)]
+    pub inputs: BTreeMap<String, RecipeInput>,
+    pub states: Vec<RecipeState>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum RecipeInput {
+    State { dmi: PathBuf, state: String },
+    Png { png: PathBuf },
+    Matrix { matrix: Vec<f32> },
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RecipeState {
+    pub name: String,
+    /// Input the state starts from, a blank image if missing.
+    pub from: Option<String>,
+    #[serde(default)]
+    pub steps: Vec<RecipeStep>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RecipeColorPair {
+    pub from: Vec<u8>,
+    pub to: Vec<u8>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum RecipeStep {
+    Blend {
+        input: String,
+        mode: String,
+        #[serde(default = "default_offset")]
+        x: i64,
+        #[serde(default = "default_offset")]
+        y: i64,
+    },
+    BlendColor {
+        color: Vec<u8>,
+        mode: String,
+    },
+    ColorMatrix {
+        input: Option<String>,
+        matrix: Option<Vec<f32>>,
+    },
+    ReplaceColors {
+        map: Vec<RecipeColorPair>,
+        #[serde(default)]
+        tolerance: u8,
+    },
+    Shift {
+        dir: String,
+        offset: i64,
+        #[serde(default)]
+        wrap: bool,
+    },
+    Turn {
+        angle: f64,
+        #[serde(default = "default_mode")]
+        mode: String,
+    },
+    Flip {
+        dir: String,
+        #[serde(default = "default_mode")]
+        mode: String,
+    },
+    Scale {
+        width: u32,
+        height: u32,
+        #[serde(default = "default_mode")]
+        mode: String,
+    },
+    Resize {
+        width: u32,
+        height: u32,
+        method: String,
+    },
+    Set {
+        name: Option<String>,
+        dirs: Option<u32>,
+        delays: Option<Vec<f32>>,
+        #[serde(rename = "loop")]
+        loop_: Option<u32>,
+        rewind: Option<bool>,
+        movement: Option<bool>,
+        hotspots: Option<Vec<String>>,
+    },
+}
+
+fn default_offset() -> i64 {
+    1
+}
+
+fn default_mode() -> String {
+    "byond".to_string()
+}
+
+enum LoadedInput {
+    State(State),
+    Matrix(ColorMatrix),
+}
+
+impl Recipe {
+    pub fn open<P>(path: P) -> DmiResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(serde_json::from_str(&read_to_string(path)?)?)
+    }
+    /// Builds the DMI, resolving input paths relative to `base`.
+    pub fn build<P>(&self, base: P) -> DmiResult<Dmi>
+    where
+        P: AsRef<Path>,
+    {
+        let mut inputs = HashMap::new();
+
+        for (name, input) in self.inputs.iter() {
+            let loaded = input
+                .load(base.as_ref())
+                .map_err(|source| DmiError::RecipeInput {
+                    input: name.clone(),
+                    source: Box::new(source),
+                })?;
+            inputs.insert(name.as_str(), loaded);
+        }
+
+        let mut dmi = Dmi::new(self.name.clone(), self.width, self.height);
+
+        for recipe_state in self.states.iter() {
+            let mut state = match &recipe_state.from {
+                Some(from) => match inputs.get(from.as_str()) {
+                    Some(LoadedInput::State(state)) => state.clone(),
+                    Some(LoadedInput::Matrix(_)) => {
+                        return Err(DmiError::InvalidRecipeInput(from.clone()))
+                    }
+                    None => return Err(DmiError::MissingRecipeInput(from.clone())),
+                },
+                None => State::new_blank(String::new(), self.width, self.height),
+            };
+
+            state.name = recipe_state.name.clone();
+
+            for (index, step) in recipe_state.steps.iter().enumerate() {
+                step.apply(&mut state, &inputs)
+                    .map_err(|source| DmiError::RecipeStep {
+                        state: recipe_state.name.clone(),
+                        step: index + 1,
+                        op: step.op(),
+                        source: Box::new(source),
+                    })?;
+            }
+
+            if let Some(frame) = state.frames.first() {
+                if frame.width() != self.width || frame.height() != self.height {
+                    return Err(DmiError::RecipeSizeMismatch {
+                        state: recipe_state.name.clone(),
+                        width: frame.width(),
+                        height: frame.height(),
+                        expected_width: self.width,
+                        expected_height: self.height,
+                    });
+                }
+            }
+
+            dmi.states.push(state);
+        }
+
+        Ok(dmi)
+    }
+}
+
+impl RecipeInput {
+    fn load(&self, base: &Path) -> DmiResult<LoadedInput> {
+        match self {
+            Self::State { dmi, state } => {
+                let dmi = Dmi::open(base.join(dmi))?;
+                let state = dmi
+                    .states
+                    .into_iter()
+                    .find(|candidate| candidate.name == *state)
+                    .ok_or_else(|| DmiError::StateNotFound(state.clone()))?;
+                Ok(LoadedInput::State(state))
+            }
+            Self::Png { png } => {
+                let mut reader = ImageReader::open(base.join(png))?;
+                reader.set_format(image::ImageFormat::Png);
+                let image = reader.decode()?;
+
+                let mut state = State::new_blank(String::new(), image.width(), image.height());
+                state.frames[0] = image;
+
+                Ok(LoadedInput::State(state))
+            }
+            Self::Matrix { matrix } => Ok(LoadedInput::Matrix(ColorMatrix::from_values(matrix)?)),
+        }
+    }
+}
+
+impl RecipeStep {
+    pub fn op(&self) -> &'static str {
+        match self {
+            Self::Blend { .. } => "blend",
+            Self::BlendColor { .. } => "blend_color",
+            Self::ColorMatrix { .. } => "color_matrix",
+            Self::ReplaceColors { .. } => "replace_colors",
+            Self::Shift { .. } => "shift",
+            Self::Turn { .. } => "turn",
+            Self::Flip { .. } => "flip",
+            Self::Scale { .. } => "scale",
+            Self::Resize { .. } => "resize",
+            Self::Set { .. } => "set",
+        }
+    }
+    fn apply(&self, state: &mut State, inputs: &HashMap<&str, LoadedInput>) -> DmiResult<()> {
+        match self {
+            Self::Blend { input, mode, x, y } => {
+                let LoadedInput::State(other) = get_input(inputs, input)? else {
+                    return Err(DmiError::InvalidRecipeInput(input.clone()));
+                };
+                state.blend(other, mode.parse::<BlendMode>()?, *x, *y)?;
+            }
+            Self::BlendColor { color, mode } => {
+                state.blend_color(recipe_color(color)?, mode.parse::<BlendMode>()?);
+            }
+            Self::ColorMatrix { input, matrix } => {
+                let matrix = match (input, matrix) {
+                    (Some(input), _) => match get_input(inputs, input)? {
+                        LoadedInput::Matrix(matrix) => *matrix,
+                        LoadedInput::State(_) => {
+                            return Err(DmiError::InvalidRecipeInput(input.clone()))
+                        }
+                    },
+                    (None, Some(matrix)) => ColorMatrix::from_values(matrix)?,
+                    (None, None) => return Err(DmiError::MissingColorMatrix),
+                };
+                state.map_colors(&matrix);
+            }
+            Self::ReplaceColors { map, tolerance } => {
+                let map = map
+                    .iter()
+                    .map(|pair| Ok((recipe_color(&pair.from)?, recipe_color(&pair.to)?)))
+                    .collect::<DmiResult<Vec<_>>>()?;
+                state.replace_colors(&map, *tolerance, &Scope::default(), false);
+            }
+            Self::Shift { dir, offset, wrap } => {
+                state.shift(dir.parse::<Direction>()?, *offset, *wrap);
+            }
+            Self::Turn { angle, mode } => {
+                state.turn(*angle, mode.parse::<GeometryMode>()?);
+            }
+            Self::Flip { dir, mode } => {
+                state.flip(dir.parse::<Direction>()?, mode.parse::<GeometryMode>()?)?;
+            }
+            Self::Scale {
+                width,
+                height,
+                mode,
+            } => {
+                state.scale(*width, *height, mode.parse::<GeometryMode>()?);
+            }
+            Self::Resize {
+                width,
+                height,
+                method,
+            } => {
+                state.resize(*width, *height, method.parse::<ResizeMethod>()?)?;
+            }
+            Self::Set {
+                name,
+                dirs,
+                delays,
+                loop_,
+                rewind,
+                movement,
+                hotspots,
+            } => {
+                if let Some(name) = name {
+                    state.name = name.clone();
+                }
+                if let Some(dirs) = dirs {
+                    state.set_dirs(*dirs)?;
+                }
+                if let Some(delays) = delays {
+                    if delays.len() != state.frame_count as usize {
+                        return Err(DmiError::DelayCountMismatch {
+                            expected: state.frame_count as usize,
+                            got: delays.len(),
+                        });
+                    }
+                    state.delays = delays.clone();
+                }
+                if let Some(loop_) = loop_ {
+                    state.loop_ = *loop_;
+                }
+                if let Some(rewind) = rewind {
+                    state.rewind = *rewind;
+                }
+                if let Some(movement) = movement {
+                    state.movement = *movement;
+                }
+                if let Some(hotspots) = hotspots {
+                    state.hotspots = hotspots.clone();
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn get_input<'a>(inputs: &'a HashMap<&str, LoadedInput>, name: &str) -> DmiResult<&'a LoadedInput> {
+    inputs
+        .get(name)
+        .ok_or_else(|| DmiError::MissingRecipeInput(name.to_string()))
+}
+
+fn recipe_color(color: &[u8]) -> DmiResult<Rgba<u8>> {
+    match *color {
+        [r, g, b] => Ok(Rgba([r, g, b, 255])),
+        [r, g, b, a] => Ok(Rgba([r, g, b, a])),
+        _ => Err(DmiError::InvalidColor),
+    }
+}
+
+impl Dmi {
+    pub fn build_from_recipe<P>(path: P) -> DmiResult<Dmi>
+    where
+        P: AsRef<Path>,
+    {
+        let recipe = Recipe::open(&path)?;
+        let base = path.as_ref().parent().unwrap_or(Path::new("."));
+
+        recipe.build(base)
+    }
+}
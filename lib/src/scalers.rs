@@ -0,0 +1,372 @@
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::str::FromStr;
+
+use crate::dmi::{DmiError, DmiResult};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResizeMethod {
+    Filter(FilterType),
+    PixelArt(PixelScaler),
+}
+
+impl ResizeMethod {
+    pub const NAMES: [&'static str; 9] = [
+        "nearest",
+        "triangle",
+        "catmullrom",
+        "gaussian",
+        "lanczos3",
+        "scale2x",
+        "scale3x",
+        "scale4x",
+        "xbr2x",
+    ];
+
+    pub fn resize(self, image: &DynamicImage, width: u32, height: u32) -> DmiResult<DynamicImage> {
+        match self {
+            Self::Filter(filter) => Ok(image.resize_exact(width, height, filter)),
+            Self::PixelArt(scaler) => scaler.resize(image, width, height),
+        }
+    }
+}
+
+impl FromStr for ResizeMethod {
+    type Err = DmiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(Self::Filter(FilterType::Nearest)),
+            "triangle" => Ok(Self::Filter(FilterType::Triangle)),
+            "catmullrom" => Ok(Self::Filter(FilterType::CatmullRom)),
+            "gaussian" => Ok(Self::Filter(FilterType::Gaussian)),
+            "lanczos3" => Ok(Self::Filter(FilterType::Lanczos3)),
+            "scale2x" => Ok(Self::PixelArt(PixelScaler::Scale2x)),
+            "scale3x" => Ok(Self::PixelArt(PixelScaler::Scale3x)),
+            "scale4x" => Ok(Self::PixelArt(PixelScaler::Scale4x)),
+            "xbr2x" => Ok(Self::PixelArt(PixelScaler::Xbr2x)),
+            _ => Err(DmiError::UnknownResizeMethod(s.to_string())),
+        }
+    }
+}
+
+/// Upscaling algorithms made for pixel art.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelScaler {
+    /// Scale2x, also known as EPX.
+    Scale2x,
+    Scale3x,
+    /// Scale2x applied twice.
+    Scale4x,
+    /// Hyllian's 2xBR, smooths diagonal edges by blending the corner pixels.
+    Xbr2x,
+}
+
+impl PixelScaler {
+    pub fn factor(self) -> u32 {
+        match self {
+            Self::Scale2x | Self::Xbr2x => 2,
+            Self::Scale3x => 3,
+            Self::Scale4x => 4,
+        }
+    }
+    pub fn scale(self, image: &RgbaImage) -> RgbaImage {
+        match self {
+            Self::Scale2x => scale2x(image),
+            Self::Scale3x => scale3x(image),
+            Self::Scale4x => scale2x(&scale2x(image)),
+            Self::Xbr2x => xbr2x(image),
+        }
+    }
+    /// Number of times the scaler has to be applied to turn a `width` x `height` image into
+    /// a `target_width` x `target_height` one, `None` if no number of steps gets there.
+    pub fn steps(
+        self,
+        width: u32,
+        height: u32,
+        target_width: u32,
+        target_height: u32,
+    ) -> Option<u32> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let (mut width, mut height, mut steps) = (width, height, 0);
+
+        while (width, height) != (target_width, target_height) {
+            if width >= target_width || height >= target_height {
+                return None;
+            }
+            width = width.checked_mul(self.factor())?;
+            height = height.checked_mul(self.factor())?;
+            steps += 1;
+        }
+
+        Some(steps)
+    }
+    /// Applies the scaler until the image is `width` x `height`, which must be the size of
+    /// the image multiplied by a power of the factor.
+    pub fn resize(self, image: &DynamicImage, width: u32, height: u32) -> DmiResult<DynamicImage> {
+        let steps = self
+            .steps(image.width(), image.height(), width, height)
+            .ok_or(DmiError::InvalidScaleSize {
+                width: image.width(),
+                height: image.height(),
+                target_width: width,
+                target_height: height,
+                factor: self.factor(),
+            })?;
+
+        let mut image = image.to_rgba8();
+        for _ in 0..steps {
+            image = self.scale(&image);
+        }
+
+        Ok(DynamicImage::ImageRgba8(image))
+    }
+}
+
+fn pixel(image: &RgbaImage, x: i64, y: i64) -> Rgba<u8> {
+    let x = x.clamp(0, image.width() as i64 - 1) as u32;
+    let y = y.clamp(0, image.height() as i64 - 1) as u32;
+    *image.get_pixel(x, y)
+}
+
+pub fn scale2x(image: &RgbaImage) -> RgbaImage {
+    let mut output = RgbaImage::new(image.width() * 2, image.height() * 2);
+
+    for (x, y, &p) in image.enumerate_pixels() {
+        let (ix, iy) = (x as i64, y as i64);
+        let a = pixel(image, ix, iy - 1);
+        let b = pixel(image, ix + 1, iy);
+        let c = pixel(image, ix - 1, iy);
+        let d = pixel(image, ix, iy + 1);
+
+        let (x, y) = (x * 2, y * 2);
+
+        let top_left = if c == a && c != d && a != b { a } else { p };
+        let top_right = if a == b && a != c && b != d { b } else { p };
+        let bottom_left = if d == c && d != b && c != a { c } else { p };
+        let bottom_right = if b == d && b != a && d != c { d } else { p };
+
+        output.put_pixel(x, y, top_left);
+        output.put_pixel(x + 1, y, top_right);
+        output.put_pixel(x, y + 1, bottom_left);
+        output.put_pixel(x + 1, y + 1, bottom_right);
+    }
+
+    output
+}
+
+pub fn scale3x(image: &RgbaImage) -> RgbaImage {
+    let mut output = RgbaImage::new(image.width() * 3, image.height() * 3);
+
+    for (x, y, &e) in image.enumerate_pixels() {
+        let (ix, iy) = (x as i64, y as i64);
+        let a = pixel(image, ix - 1, iy - 1);
+        let b = pixel(image, ix, iy - 1);
+        let c = pixel(image, ix + 1, iy - 1);
+        let d = pixel(image, ix - 1, iy);
+        let f = pixel(image, ix + 1, iy);
+        let g = pixel(image, ix - 1, iy + 1);
+        let h = pixel(image, ix, iy + 1);
+        let i = pixel(image, ix + 1, iy + 1);
+
+        let block = if b != h && d != f {
+            [
+                if d == b { d } else { e },
+                if (d == b && e != c) || (b == f && e != a) {
+                    b
+                } else {
+                    e
+                },
+                if b == f { f } else { e },
+                if (d == b && e != g) || (d == h && e != a) {
+                    d
+                } else {
+                    e
+                },
+                e,
+                if (b == f && e != i) || (h == f && e != c) {
+                    f
+                } else {
+                    e
+                },
+                if d == h { d } else { e },
+                if (d == h && e != i) || (h == f && e != g) {
+                    h
+                } else {
+                    e
+                },
+                if h == f { f } else { e },
+            ]
+        } else {
+            [e; 9]
+        };
+
+        for (index, pixel) in block.into_iter().enumerate() {
+            let index = index as u32;
+            output.put_pixel(x * 3 + index % 3, y * 3 + index / 3, pixel);
+        }
+    }
+
+    output
+}
+
+pub fn xbr2x(image: &RgbaImage) -> RgbaImage {
+    let mut output = RgbaImage::new(image.width() * 2, image.height() * 2);
+
+    for (x, y, &e) in image.enumerate_pixels() {
+        let mut block = [e; 4];
+
+        // The corner filter is written for the bottom-right sub-pixel, the neighbourhood
+        // and the sub-pixels are rotated by a quarter turn for each of the other corners.
+        for rotation in 0..4 {
+            let get = |dx: i64, dy: i64| {
+                let (dx, dy) = rotate(dx, dy, rotation);
+                pixel(image, x as i64 + dx, y as i64 + dy)
+            };
+            let sub_pixel = |dx: i64, dy: i64| {
+                let (dx, dy) = rotate(dx, dy, rotation);
+                (dx.max(0) + dy.max(0) * 2) as usize
+            };
+
+            xbr_corner(
+                &mut block,
+                [sub_pixel(1, 1), sub_pixel(-1, 1), sub_pixel(1, -1)],
+                XbrNeighbourhood {
+                    e,
+                    i: get(1, 1),
+                    h: get(0, 1),
+                    f: get(1, 0),
+                    g: get(-1, 1),
+                    c: get(1, -1),
+                    d: get(-1, 0),
+                    b: get(0, -1),
+                    h5: get(0, 2),
+                    f4: get(2, 0),
+                    i5: get(1, 2),
+                    i4: get(2, 1),
+                },
+            );
+        }
+
+        for (index, pixel) in block.into_iter().enumerate() {
+            let index = index as u32;
+            output.put_pixel(x * 2 + index % 2, y * 2 + index / 2, pixel);
+        }
+    }
+
+    output
+}
+
+struct XbrNeighbourhood {
+    e: Rgba<u8>,
+    i: Rgba<u8>,
+    h: Rgba<u8>,
+    f: Rgba<u8>,
+    g: Rgba<u8>,
+    c: Rgba<u8>,
+    d: Rgba<u8>,
+    b: Rgba<u8>,
+    h5: Rgba<u8>,
+    f4: Rgba<u8>,
+    i5: Rgba<u8>,
+    i4: Rgba<u8>,
+}
+
+fn xbr_corner(block: &mut [Rgba<u8>; 4], [corner, left, up]: [usize; 3], n: XbrNeighbourhood) {
+    if n.e == n.h || n.e == n.f {
+        return;
+    }
+
+    let e = distance(n.e, n.c)
+        + distance(n.e, n.g)
+        + distance(n.i, n.h5)
+        + distance(n.i, n.f4)
+        + 4. * distance(n.h, n.f);
+    let i = distance(n.h, n.d)
+        + distance(n.h, n.i5)
+        + distance(n.f, n.i4)
+        + distance(n.f, n.b)
+        + 4. * distance(n.e, n.i);
+
+    if e > i {
+        return;
+    }
+
+    let new = if distance(n.e, n.f) <= distance(n.e, n.h) {
+        n.f
+    } else {
+        n.h
+    };
+
+    let sharp = (!similar(n.f, n.b) && !similar(n.h, n.d))
+        || (similar(n.e, n.i) && !similar(n.f, n.i4) && !similar(n.h, n.i5))
+        || similar(n.e, n.g)
+        || similar(n.e, n.c);
+
+    if e < i && sharp {
+        let ke = distance(n.f, n.g);
+        let ki = distance(n.h, n.c);
+        let ex = n.e != n.g && n.d != n.g;
+        let ex2 = n.e != n.c && n.b != n.c;
+
+        let shallow = ke * 2. <= ki && ex;
+        let steep = ke >= ki * 2. && ex2;
+
+        if shallow || steep {
+            if shallow {
+                block[corner] = blend(block[corner], new, 0.75);
+                block[left] = blend(block[left], new, 0.25);
+            }
+            if steep {
+                block[corner] = blend(block[corner], new, 0.75);
+                block[up] = blend(block[up], new, 0.25);
+            }
+        } else {
+            block[corner] = blend(block[corner], new, 0.5);
+        }
+    } else {
+        block[corner] = blend(block[corner], new, 0.25);
+    }
+}
+
+/// Rotates an offset clockwise by `rotation` quarter turns.
+fn rotate(dx: i64, dy: i64, rotation: u32) -> (i64, i64) {
+    (0..rotation).fold((dx, dy), |(dx, dy), _| (-dy, dx))
+}
+
+fn yuva(pixel: Rgba<u8>) -> [f32; 4] {
+    let [r, g, b, a] = pixel.0.map(|channel| channel as f32);
+    [
+        0.299 * r + 0.587 * g + 0.114 * b,
+        -0.169 * r - 0.331 * g + 0.5 * b,
+        0.5 * r - 0.419 * g - 0.081 * b,
+        a,
+    ]
+}
+
+fn distance(a: Rgba<u8>, b: Rgba<u8>) -> f32 {
+    let (a, b) = (yuva(a), yuva(b));
+    48. * (a[0] - b[0]).abs()
+        + 7. * (a[1] - b[1]).abs()
+        + 6. * (a[2] - b[2]).abs()
+        + 48. * (a[3] - b[3]).abs()
+}
+
+fn similar(a: Rgba<u8>, b: Rgba<u8>) -> bool {
+    let (a, b) = (yuva(a), yuva(b));
+    (a[0] - b[0]).abs() <= 48.
+        && (a[1] - b[1]).abs() <= 7.
+        && (a[2] - b[2]).abs() <= 6.
+        && a[3] == b[3]
+}
+
+fn blend(a: Rgba<u8>, b: Rgba<u8>, amount: f32) -> Rgba<u8> {
+    let mut output = a;
+    for (channel, (a, b)) in output.0.iter_mut().zip(a.0.iter().zip(b.0.iter())) {
+        *channel = (*a as f32 + (*b as f32 - *a as f32) * amount).round() as u8;
+    }
+    output
+}
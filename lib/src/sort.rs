@@ -0,0 +1,116 @@
+//! Ordering the states of a DMI.
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::dmi::{Dmi, DmiError, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Plain comparison of the names.
+    Alphabetical,
+    /// Numbers in the names are compared by value, "state2" comes before "state10".
+    Natural,
+}
+
+impl FromStr for SortOrder {
+    type Err = DmiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "alphabetical" => Ok(Self::Alphabetical),
+            "natural" => Ok(Self::Natural),
+            _ => Err(DmiError::UnknownSortOrder(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub order: SortOrder,
+    /// Puts all movement states after the non-movement ones, otherwise movement states
+    /// stay next to the state with the same name.
+    pub movement_last: bool,
+}
+
+impl Default for SortKey {
+    fn default() -> Self {
+        Self {
+            order: SortOrder::Natural,
+            movement_last: false,
+        }
+    }
+}
+
+impl SortKey {
+    pub fn compare(&self, a: (&str, bool), b: (&str, bool)) -> Ordering {
+        let movement = if self.movement_last {
+            a.1.cmp(&b.1)
+        } else {
+            Ordering::Equal
+        };
+
+        movement.then_with(|| match self.order {
+            SortOrder::Alphabetical => a.0.cmp(b.0),
+            SortOrder::Natural => natural_cmp(a.0, b.0),
+        })
+    }
+}
+
+/// Compares runs of digits by value and everything else by character, falling back to
+/// a plain comparison so names like "a1" and "a01" still have a fixed order.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_chars, mut b_chars) = (a.chars().peekable(), b.chars().peekable());
+
+    loop {
+        let ordering = match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_char), Some(b_char)) if a_char.is_ascii_digit() && b_char.is_ascii_digit() => {
+                let a_digits = take_digits(&mut a_chars);
+                let b_digits = take_digits(&mut b_chars);
+                let (a_digits, b_digits) = (
+                    a_digits.trim_start_matches('0'),
+                    b_digits.trim_start_matches('0'),
+                );
+                a_digits
+                    .len()
+                    .cmp(&b_digits.len())
+                    .then_with(|| a_digits.cmp(b_digits))
+            }
+            (Some(a_char), Some(b_char)) => {
+                let ordering = a_char.cmp(b_char);
+                a_chars.next();
+                b_chars.next();
+                ordering
+            }
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(char) = chars.next_if(char::is_ascii_digit) {
+        digits.push(char);
+    }
+    digits
+}
+
+impl Dmi {
+    /// Sorts the states by the key, states that compare equal keep their order.
+    pub fn sort_states(&mut self, key: SortKey) {
+        self.sort_states_by(|a, b| key.compare((&a.name, a.movement), (&b.name, b.movement)));
+    }
+    /// Sorts the states with a custom comparator, states that compare equal keep their order.
+    pub fn sort_states_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&State, &State) -> Ordering,
+    {
+        self.states.sort_by(compare);
+    }
+}
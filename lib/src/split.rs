@@ -0,0 +1,87 @@
+//! Splitting a DMI into several DMIs by the names of its states.
+
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::dmi::{Dmi, DmiResult};
+
+#[derive(Debug, Clone)]
+pub enum SplitRule {
+    /// States starting with one of the prefixes go to a DMI named after the first matching
+    /// prefix, trailing `_` and `-` are left out of the name.
+    Prefix(Vec<String>),
+    /// States matching the expression go to a DMI named after the first capture group,
+    /// or the `dmi` group if the expression has one.
+    Regex(Regex),
+    /// State names mapped to the name of their DMI.
+    Mapping(HashMap<String, String>),
+}
+
+impl SplitRule {
+    pub fn regex(pattern: &str) -> DmiResult<Self> {
+        Ok(Self::Regex(Regex::new(pattern)?))
+    }
+    /// Name of the DMI the state goes to, `None` if the rule does not match it.
+    pub fn target(&self, state: &str) -> Option<String> {
+        match self {
+            Self::Prefix(prefixes) => prefixes
+                .iter()
+                .find(|prefix| state.starts_with(prefix.as_str()))
+                .map(|prefix| prefix.trim_end_matches(['_', '-']).to_string()),
+            Self::Regex(regex) => {
+                let captures = regex.captures(state)?;
+                captures
+                    .name("dmi")
+                    .or_else(|| captures.get(1))
+                    .map(|capture| capture.as_str().to_string())
+                    .filter(|name| !name.is_empty())
+            }
+            Self::Mapping(mapping) => mapping.get(state).cloned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplitReport {
+    /// Every state and the name of the DMI it went to, in the original order.
+    pub moves: Vec<(String, String)>,
+}
+
+impl SplitReport {
+    /// States that left the original DMI.
+    pub fn moved_from<'a>(
+        &'a self,
+        original: &'a str,
+    ) -> impl Iterator<Item = &'a (String, String)> {
+        self.moves.iter().filter(move |(_, dmi)| dmi != original)
+    }
+}
+
+impl Dmi {
+    /// Splits the states by the rule, states the rule does not match stay in a DMI with
+    /// the original name. The DMIs are returned in the order of their first state and
+    /// every DMI keeps the order of its states.
+    pub fn split_by(&self, rule: &SplitRule) -> (Vec<Dmi>, SplitReport) {
+        let mut dmis: Vec<Dmi> = Vec::new();
+        let mut report = SplitReport::default();
+
+        for state in self.states.iter() {
+            let target = rule
+                .target(&state.name)
+                .unwrap_or_else(|| self.name.clone());
+
+            let dmi = match dmis.iter().position(|dmi| dmi.name == target) {
+                Some(index) => &mut dmis[index],
+                None => {
+                    dmis.push(Dmi::new(target.clone(), self.width, self.height));
+                    dmis.last_mut().unwrap()
+                }
+            };
+
+            dmi.states.push(state.clone());
+            report.moves.push((state.name.clone(), target));
+        }
+
+        (dmis, report)
+    }
+}
@@ -0,0 +1,207 @@
+//! Splitting large icons into tile sized states and joining them back.
+//!
+//! Tile states are named with a pattern where `{state}` is the name of the large state
+//! and `{x}`, `{y}` the column and row of the tile, counted from the top-left corner.
+
+use image::{imageops, DynamicImage};
+
+use crate::dmi::{shift_hotspot, Dmi, DmiError, DmiResult};
+
+pub const DEFAULT_TILE_NAMING: &str = "{state}_x{x}_y{y}";
+
+pub fn tile_name(naming: &str, state: &str, x: u32, y: u32) -> String {
+    naming
+        .replace("{state}", state)
+        .replace("{x}", &x.to_string())
+        .replace("{y}", &y.to_string())
+}
+
+/// Reverses `tile_name`, returning the state name and tile position.
+pub fn parse_tile_name(naming: &str, name: &str) -> Option<(String, u32, u32)> {
+    let mut captures = TileCaptures::default();
+    if match_pattern(naming, name, &mut captures) {
+        Some((captures.state?, captures.x?, captures.y?))
+    } else {
+        None
+    }
+}
+
+/// A joined state name and the column, row and state index of its tiles.
+type TileGroup = (String, Vec<(u32, u32, usize)>);
+
+#[derive(Default, Clone)]
+struct TileCaptures {
+    state: Option<String>,
+    x: Option<u32>,
+    y: Option<u32>,
+}
+
+/// Matches `name` against the rest of the pattern, backtracking over the length of `{state}`.
+fn match_pattern(pattern: &str, name: &str, captures: &mut TileCaptures) -> bool {
+    let Some(start) = pattern.find('{') else {
+        return pattern == name;
+    };
+
+    let (literal, rest) = pattern.split_at(start);
+    let Some(name) = name.strip_prefix(literal) else {
+        return false;
+    };
+
+    for placeholder in ["{state}", "{x}", "{y}"] {
+        let Some(rest) = rest.strip_prefix(placeholder) else {
+            continue;
+        };
+
+        if placeholder == "{state}" {
+            for end in (1..=name.len()).filter(|end| name.is_char_boundary(*end)) {
+                let mut attempt = captures.clone();
+                attempt.state = Some(name[..end].to_string());
+                if match_pattern(rest, &name[end..], &mut attempt) {
+                    *captures = attempt;
+                    return true;
+                }
+            }
+            return false;
+        }
+
+        let digits = name.len() - name.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let Ok(value) = name[..digits].parse::<u32>() else {
+            return false;
+        };
+        if placeholder == "{x}" {
+            captures.x = Some(value);
+        } else {
+            captures.y = Some(value);
+        }
+        return match_pattern(rest, &name[digits..], captures);
+    }
+
+    // Not a placeholder, match the brace literally.
+    match (rest.strip_prefix('{'), name.strip_prefix('{')) {
+        (Some(rest), Some(name)) => match_pattern(rest, name, captures),
+        _ => false,
+    }
+}
+
+impl Dmi {
+    /// Cuts every state into `tile_width` x `tile_height` states, named after `naming`.
+    pub fn split_tiles(
+        &mut self,
+        tile_width: u32,
+        tile_height: u32,
+        naming: &str,
+    ) -> DmiResult<()> {
+        if tile_width == 0
+            || tile_height == 0
+            || !self.width.is_multiple_of(tile_width)
+            || !self.height.is_multiple_of(tile_height)
+        {
+            return Err(DmiError::InvalidTileSize {
+                width: self.width,
+                height: self.height,
+                tile_width,
+                tile_height,
+            });
+        }
+
+        let (columns, rows) = (self.width / tile_width, self.height / tile_height);
+        let mut states = Vec::with_capacity(self.states.len() * (columns * rows) as usize);
+
+        for state in self.states.iter() {
+            for y in 0..rows {
+                for x in 0..columns {
+                    let (left, top) = ((x * tile_width) as i64, (y * tile_height) as i64);
+
+                    let mut tile = state.clone();
+                    tile.name = tile_name(naming, &state.name, x, y);
+                    // Hotspots outside of the tile are dropped, they stay with the tile they are on.
+                    tile.resize_canvas(tile_width, tile_height, -left, -top);
+
+                    states.push(tile);
+                }
+            }
+        }
+
+        self.states = states;
+        self.width = tile_width;
+        self.height = tile_height;
+
+        Ok(())
+    }
+    /// Puts the tiles of `split_tiles` back together, states are kept in the order of
+    /// their first tile. All states must be tiles and make up grids of the same size.
+    pub fn join_tiles(&mut self, naming: &str) -> DmiResult<()> {
+        let mut groups: Vec<TileGroup> = Vec::new();
+
+        for (index, state) in self.states.iter().enumerate() {
+            let (name, x, y) = parse_tile_name(naming, &state.name)
+                .ok_or_else(|| DmiError::InvalidTileName(state.name.clone()))?;
+
+            match groups.iter_mut().find(|(group, _)| *group == name) {
+                Some((_, tiles)) => tiles.push((x, y, index)),
+                None => groups.push((name, vec![(x, y, index)])),
+            }
+        }
+
+        let grid = |tiles: &[(u32, u32, usize)]| {
+            (
+                tiles.iter().map(|(x, _, _)| x + 1).max().unwrap_or(0),
+                tiles.iter().map(|(_, y, _)| y + 1).max().unwrap_or(0),
+            )
+        };
+
+        let Some((columns, rows)) = groups.first().map(|(_, tiles)| grid(tiles)) else {
+            return Ok(());
+        };
+
+        let (width, height) = (self.width * columns, self.height * rows);
+        let mut states = Vec::with_capacity(groups.len());
+
+        for (name, tiles) in groups {
+            if grid(&tiles) != (columns, rows) {
+                return Err(DmiError::TileGridMismatch(name));
+            }
+
+            let first = &self.states[tiles[0].2];
+            let mut state = first.clone();
+            state.name = name.clone();
+            state.hotspots.clear();
+            for frame in state.frames.iter_mut() {
+                *frame = DynamicImage::new_rgba8(width, height);
+            }
+
+            for y in 0..rows {
+                for x in 0..columns {
+                    let Some((_, _, index)) = tiles.iter().find(|tile| (tile.0, tile.1) == (x, y))
+                    else {
+                        return Err(DmiError::MissingTile { state: name, x, y });
+                    };
+
+                    let tile = &self.states[*index];
+                    if tile.dirs != first.dirs || tile.frame_count != first.frame_count {
+                        return Err(DmiError::TileGridMismatch(name));
+                    }
+
+                    let (left, top) = ((x * self.width) as i64, (y * self.height) as i64);
+
+                    for (frame, tile_frame) in state.frames.iter_mut().zip(tile.frames.iter()) {
+                        imageops::replace(frame, tile_frame, left, top);
+                    }
+                    state.hotspots.extend(
+                        tile.hotspots
+                            .iter()
+                            .filter_map(|hotspot| shift_hotspot(hotspot, left, top, width, height)),
+                    );
+                }
+            }
+
+            states.push(state);
+        }
+
+        self.states = states;
+        self.width = width;
+        self.height = height;
+
+        Ok(())
+    }
+}
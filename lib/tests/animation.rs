@@ -0,0 +1,244 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use dmi::{DedupeReport, Dmi, DmiError, State};
+
+fn image(value: u8) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+        2,
+        2,
+        Rgba([value, value, value, 255]),
+    ))
+}
+
+/// A two direction state whose frames are made of the given values, the second
+/// direction is the value plus 100.
+fn animated(values: &[u8], delays: &[f32]) -> State {
+    let mut state = State::new_blank("anim".to_string(), 2, 2);
+    state.dirs = 2;
+    state.frame_count = values.len() as u32;
+    state.frames = values
+        .iter()
+        .flat_map(|value| [image(*value), image(value + 100)])
+        .collect();
+    state.delays = delays.to_vec();
+    state
+}
+
+fn values(state: &State) -> Vec<u8> {
+    state
+        .frames
+        .iter()
+        .step_by(state.dirs as usize)
+        .map(|frame| frame.to_rgba8().get_pixel(0, 0)[0])
+        .collect()
+}
+
+#[test]
+fn dedupe_frames() {
+    let mut state = animated(&[1, 1, 1, 2, 3, 3, 1], &[1., 2., 1., 1., 0.5, 0.5, 3.]);
+    state.frames[9] = image(50);
+
+    let report = state.duplicate_frames();
+    assert_eq!(
+        report,
+        DedupeReport {
+            frames_before: 7,
+            frames_after: 5,
+            runs: vec![(0, 3)],
+        }
+    );
+    assert_eq!(state.frame_count, 7);
+
+    // The second direction of frame 4 differs, so frames 4 and 5 stay apart.
+    assert_eq!(state.dedupe_frames(), report);
+    assert_eq!(state.frame_count, 5);
+    assert_eq!(state.frames.len(), 10);
+    assert_eq!(values(&state), vec![1, 2, 3, 3, 1]);
+    assert_eq!(state.delays, vec![4., 1., 0.5, 0.5, 3.]);
+    assert_eq!(state.frames[1].to_rgba8().get_pixel(0, 0)[0], 101);
+
+    assert!(state.dedupe_frames().runs.is_empty());
+}
+
+#[test]
+fn dedupe_hotspots() {
+    let mut state = animated(&[1, 1, 2, 2], &[1., 1., 1., 1.]);
+    // Frame 3 has a different hotspot than frame 2, in its second direction.
+    state.hotspots = vec![
+        "1,1,1".to_string(),
+        "1,1,3".to_string(),
+        "0,1,6".to_string(),
+    ];
+
+    state.dedupe_frames();
+
+    assert_eq!(state.frame_count, 3);
+    assert_eq!(values(&state), vec![1, 2, 2]);
+    assert_eq!(state.delays, vec![2., 1., 1.]);
+    assert_eq!(
+        state.hotspots,
+        vec!["1,1,1".to_string(), "0,1,4".to_string()]
+    );
+}
+
+#[test]
+fn dedupe_rewind() {
+    // Rewinding plays the middle frames twice and the ends once, merging into an end
+    // would change how long it shows.
+    let mut ends = animated(&[1, 1, 2, 2], &[1.; 4]);
+    ends.rewind = true;
+    assert!(ends.duplicate_frames().runs.is_empty());
+    assert!(ends.dedupe_frames().runs.is_empty());
+    assert_eq!(ends.frame_count, 4);
+
+    let mut state = animated(&[1, 1, 2, 2, 2, 3, 3], &[1., 1., 1., 2., 1., 1., 1.]);
+    state.rewind = true;
+
+    let report = state.duplicate_frames();
+    assert_eq!(report.runs, vec![(2, 3)]);
+    assert_eq!(state.dedupe_frames(), report);
+    assert_eq!(values(&state), vec![1, 1, 2, 3, 3]);
+    assert_eq!(state.delays, vec![1., 1., 4., 1., 1.]);
+}
+
+#[test]
+fn dedupe_dmi() {
+    let mut dmi = Dmi::new("icon".to_string(), 2, 2);
+    dmi.states.push(State::new_blank("still".to_string(), 2, 2));
+    dmi.states.push(animated(&[1, 2, 2], &[1., 1., 1.]));
+
+    let reports = dmi.duplicate_frames();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].0, 1);
+    assert_eq!(dmi.states[1].frame_count, 3);
+
+    assert_eq!(dmi.dedupe_frames(), reports);
+    assert_eq!(dmi.states[1].delays, vec![1., 2.]);
+}
+
+#[test]
+fn resample() {
+    let mut state = animated(&[0, 1, 2, 3, 4, 5, 6, 7], &[1.; 8]);
+    state.loop_ = 3;
+    state.rewind = true;
+    state.hotspots = vec!["1,1,7".to_string(), "0,0,5".to_string()];
+
+    state.resample(4).unwrap();
+
+    // Every new frame shows the frame playing at its midpoint.
+    assert_eq!(state.frame_count, 4);
+    assert_eq!(values(&state), vec![1, 3, 5, 7]);
+    assert_eq!(state.delays, vec![2.; 4]);
+    assert_eq!(state.total_ticks(), 8.);
+    assert_eq!(state.hotspots, vec!["1,1,3".to_string()]);
+    assert_eq!((state.loop_, state.rewind), (3, true));
+
+    state.resample(8).unwrap();
+    assert_eq!(values(&state), vec![1, 1, 3, 3, 5, 5, 7, 7]);
+    assert_eq!(state.delays, vec![1.; 8]);
+
+    // Frames are picked by time, so long frames are picked more often.
+    let mut uneven = animated(&[1, 2, 3], &[4., 1., 1.]);
+    uneven.resample(3).unwrap();
+    assert_eq!(values(&uneven), vec![1, 1, 3]);
+    assert_eq!(uneven.delays, vec![2.; 3]);
+
+    uneven.resample(1).unwrap();
+    assert_eq!(values(&uneven), vec![1]);
+    assert!(uneven.delays.is_empty());
+
+    assert!(matches!(
+        uneven.resample(0),
+        Err(DmiError::InvalidFrameCount(0))
+    ));
+    assert!(matches!(
+        animated(&[], &[]).resample(2),
+        Err(DmiError::NoFrames)
+    ));
+}
+
+#[test]
+fn retime() {
+    let mut state = animated(&[1, 2, 3], &[1., 2., 1.]);
+    state.rewind = true;
+
+    state.retime(8.).unwrap();
+
+    assert_eq!(state.frame_count, 3);
+    assert_eq!(state.delays, vec![2., 4., 2.]);
+    assert_eq!(state.total_ticks(), 8.);
+    assert!(state.rewind);
+
+    assert!(matches!(
+        state.retime(0.),
+        Err(DmiError::InvalidAnimationLength(_))
+    ));
+    assert!(matches!(
+        animated(&[1, 2], &[0., 0.]).retime(4.),
+        Err(DmiError::ZeroLengthAnimation)
+    ));
+    assert!(matches!(
+        animated(&[], &[]).retime(4.),
+        Err(DmiError::NoFrames)
+    ));
+}
+
+#[test]
+fn bake_rewind() {
+    let mut state = animated(&[1, 2, 3, 4], &[1., 2., 3., 4.]);
+    state.rewind = true;
+    state.hotspots = vec!["1,1,3".to_string()];
+
+    assert!(state.bake_rewind());
+
+    assert!(!state.rewind);
+    assert_eq!(state.frame_count, 6);
+    assert_eq!(values(&state), vec![1, 2, 3, 4, 3, 2]);
+    assert_eq!(state.delays, vec![1., 2., 3., 4., 3., 2.]);
+    assert_eq!(
+        state.hotspots,
+        vec!["1,1,3".to_string(), "1,1,11".to_string()]
+    );
+    assert!(!state.bake_rewind());
+
+    assert!(state.detect_rewind());
+
+    assert!(state.rewind);
+    assert_eq!(values(&state), vec![1, 2, 3, 4]);
+    assert_eq!(state.delays, vec![1., 2., 3., 4.]);
+    assert_eq!(state.hotspots, vec!["1,1,3".to_string()]);
+}
+
+#[test]
+fn rewind_once() {
+    let mut state = animated(&[1, 2, 3], &[1., 1., 2.]);
+    state.rewind = true;
+    state.loop_ = 1;
+
+    state.bake_rewind();
+    assert_eq!(values(&state), vec![1, 2, 3, 2, 1]);
+    assert_eq!(state.delays, vec![1., 1., 2., 1., 1.]);
+
+    assert!(state.detect_rewind());
+    assert_eq!(values(&state), vec![1, 2, 3]);
+}
+
+#[test]
+fn detect_rewind() {
+    // Not a palindrome.
+    let mut state = animated(&[1, 2, 3, 1], &[1.; 4]);
+    assert!(!state.detect_rewind());
+
+    // Mirrored frames with different delays play differently.
+    let mut state = animated(&[1, 2, 3, 2], &[1., 1., 1., 2.]);
+    assert!(!state.detect_rewind());
+
+    // Too short to be worth folding.
+    let mut state = animated(&[1, 2], &[1.; 2]);
+    assert!(!state.detect_rewind());
+
+    let mut state = animated(&[1, 2, 3, 2], &[1.; 4]);
+    assert!(state.detect_rewind());
+    assert_eq!(values(&state), vec![1, 2, 3]);
+    assert!(state.rewind);
+}
@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use image::{DynamicImage, ImageBuffer, Rgba, RgbaImage};
+
+use dmi::{blend_images, BlendMode, State};
+
+const CLEAR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+#[test]
+fn blend_pixels() {
+    let destination = Rgba([200, 100, 50, 255]);
+    let source = Rgba([100, 100, 100, 128]);
+
+    assert_eq!(
+        BlendMode::Add.blend_pixel(destination, source),
+        Rgba([255, 200, 150, 128])
+    );
+    assert_eq!(
+        BlendMode::Subtract.blend_pixel(destination, source),
+        Rgba([100, 0, 0, 128])
+    );
+    assert_eq!(
+        BlendMode::Multiply.blend_pixel(destination, source),
+        Rgba([78, 39, 20, 128])
+    );
+    assert_eq!(
+        BlendMode::Overlay.blend_pixel(destination, source),
+        Rgba([150, 100, 75, 255])
+    );
+    assert_eq!(
+        BlendMode::Underlay.blend_pixel(destination, source),
+        destination
+    );
+    assert_eq!(BlendMode::Underlay.blend_pixel(CLEAR, source), source);
+    assert_eq!(BlendMode::Or.blend_pixel(CLEAR, source), source);
+    assert_eq!(BlendMode::Or.blend_pixel(destination, CLEAR), destination);
+    assert_eq!(
+        BlendMode::Or.blend_pixel(destination, source),
+        Rgba([255, 200, 150, 255])
+    );
+    assert_eq!(BlendMode::Add.blend_pixel(destination, CLEAR)[3], 0);
+
+    assert_eq!(
+        "ICON_MULTIPLY".parse::<BlendMode>().unwrap(),
+        BlendMode::Multiply
+    );
+    assert!("ICON_AND".parse::<BlendMode>().is_err());
+}
+
+#[test]
+fn blend_offsets_use_byond_coordinates() {
+    let white = Rgba([255, 255, 255, 255]);
+    let mut destination = RgbaImage::from_pixel(4, 4, CLEAR);
+    let source = RgbaImage::from_pixel(2, 2, white);
+
+    // x = 2, y = 1 puts the bottom-left corner one pixel to the right of the bottom-left.
+    blend_images(&mut destination, &source, BlendMode::Overlay, 2, 1);
+
+    assert_eq!(*destination.get_pixel(1, 3), white);
+    assert_eq!(*destination.get_pixel(2, 2), white);
+    assert_eq!(*destination.get_pixel(0, 3), CLEAR);
+    assert_eq!(*destination.get_pixel(1, 1), CLEAR);
+
+    let mut destination = RgbaImage::from_pixel(4, 4, CLEAR);
+    blend_images(&mut destination, &source, BlendMode::Overlay, 4, 4);
+    assert_eq!(*destination.get_pixel(3, 0), white);
+    assert_eq!(
+        destination
+            .pixels()
+            .filter(|pixel| **pixel == white)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn blend_states() {
+    let mut state = State::new_blank("base".to_string(), 2, 2);
+    state.set_dirs(4).unwrap();
+    for frame in state.frames.iter_mut() {
+        *frame =
+            DynamicImage::ImageRgba8(ImageBuffer::from_pixel(2, 2, Rgba([100, 100, 100, 255])));
+    }
+
+    let mut overlay = State::new_blank("overlay".to_string(), 2, 2);
+    overlay.frames[0] =
+        DynamicImage::ImageRgba8(ImageBuffer::from_pixel(2, 2, Rgba([50, 0, 0, 255])));
+
+    state.blend(&overlay, BlendMode::Add, 1, 1).unwrap();
+    for frame in state.frames.iter() {
+        assert_eq!(
+            *frame.to_rgba8().get_pixel(0, 0),
+            Rgba([150, 100, 100, 255])
+        );
+    }
+
+    state.blend_color(Rgba([128, 255, 0, 255]), BlendMode::Multiply);
+    assert_eq!(
+        *state.frames[3].to_rgba8().get_pixel(1, 1),
+        Rgba([75, 100, 0, 255])
+    );
+
+    let mut two_dirs = State::new_blank("two".to_string(), 2, 2);
+    two_dirs.set_dirs(4).unwrap();
+    let mut eight = State::new_blank("eight".to_string(), 2, 2);
+    eight.set_dirs(8).unwrap();
+    assert!(eight.blend(&two_dirs, BlendMode::Add, 1, 1).is_err());
+}
+
+/// Compares against the icons `tests/assets/blend/blend_references.dme` blends in DreamSeeker.
+#[test]
+#[ignore = "needs the DreamSeeker references written by tests/assets/blend/blend_references.dme"]
+fn blend_matches_dreamseeker() {
+    let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/assets/blend");
+    let open = |name: &str| {
+        image::open(assets.join(name))
+            .unwrap_or_else(|error| panic!("{name}: {error}"))
+            .to_rgba8()
+    };
+
+    let base = open("base.png");
+    let overlay = open("overlay.png");
+
+    for (name, mode) in [
+        ("add", BlendMode::Add),
+        ("subtract", BlendMode::Subtract),
+        ("multiply", BlendMode::Multiply),
+        ("overlay", BlendMode::Overlay),
+        ("underlay", BlendMode::Underlay),
+        ("or", BlendMode::Or),
+    ] {
+        for (x, y) in [(1, 1), (3, 2)] {
+            let reference = open(&format!("{name}_{x}_{y}.png"));
+
+            let mut blended = base.clone();
+            blend_images(&mut blended, &overlay, mode, x, y);
+
+            assert_eq!(blended.dimensions(), reference.dimensions());
+            for ((px, py, pixel), expected) in blended.enumerate_pixels().zip(reference.pixels()) {
+                // The color of fully transparent pixels is not visible and BYOND does not keep it.
+                if pixel[3] == 0 && expected[3] == 0 {
+                    continue;
+                }
+                assert_eq!(pixel, expected, "{name} at {x},{y}, pixel {px},{py}");
+            }
+        }
+    }
+}
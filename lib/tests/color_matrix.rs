@@ -0,0 +1,70 @@
+use image::{DynamicImage, ImageBuffer, Rgba};
+
+use dmi::{ColorMatrix, Dmi, Scope, State};
+
+#[test]
+fn matrix_forms() {
+    let pixel = Rgba([255, 128, 0, 200]);
+
+    let identity = ColorMatrix::from_values(&[1., 0., 0., 0., 1., 0., 0., 0., 1.]).unwrap();
+    assert_eq!(identity, ColorMatrix::IDENTITY);
+    assert_eq!(identity.apply_pixel(pixel), pixel);
+
+    // Swaps red and blue, then adds a constant to green.
+    let swap =
+        ColorMatrix::from_values(&[0., 0., 1., 0., 1., 0., 1., 0., 0., 0., 0.25, 0.]).unwrap();
+    assert_eq!(swap.apply_pixel(pixel), Rgba([0, 192, 255, 200]));
+
+    let alpha = ColorMatrix::from_values(&[
+        1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 0.5,
+    ])
+    .unwrap();
+    assert_eq!(alpha.apply_pixel(pixel), Rgba([255, 128, 0, 100]));
+
+    // Results are clamped to the valid range.
+    let mut values = [0.; 20];
+    values[0] = 2.;
+    values[16] = -1.;
+    values[19] = 1.;
+    let clamped = ColorMatrix::from_values(&values).unwrap();
+    assert_eq!(clamped.apply_pixel(pixel), Rgba([255, 0, 0, 255]));
+
+    assert!(ColorMatrix::from_values(&[1., 0., 0.]).is_err());
+}
+
+#[test]
+fn map_state_colors() {
+    let greyscale =
+        ColorMatrix::from_values(&[0.3, 0.3, 0.3, 0.59, 0.59, 0.59, 0.11, 0.11, 0.11]).unwrap();
+
+    let mut dmi = Dmi::new("matrix".to_string(), 2, 2);
+    let mut state = State::new_blank("red".to_string(), 2, 2);
+    state.frames[0] =
+        DynamicImage::ImageRgba8(ImageBuffer::from_pixel(2, 2, Rgba([255, 0, 0, 255])));
+    dmi.states.push(state);
+
+    let index = dmi
+        .map_colors_as_new_state(0, &greyscale, "grey".to_string())
+        .unwrap();
+
+    assert_eq!(index, 1);
+    assert_eq!(dmi.states[1].name, "grey");
+    assert_eq!(
+        *dmi.states[1].frames[0].to_rgba8().get_pixel(0, 0),
+        Rgba([77, 77, 77, 255])
+    );
+    assert_eq!(
+        *dmi.states[0].frames[0].to_rgba8().get_pixel(0, 0),
+        Rgba([255, 0, 0, 255])
+    );
+
+    dmi.map_colors(&greyscale, &Scope::default());
+    assert_eq!(
+        *dmi.states[0].frames[0].to_rgba8().get_pixel(0, 0),
+        Rgba([77, 77, 77, 255])
+    );
+
+    assert!(dmi
+        .map_colors_as_new_state(5, &greyscale, "missing".to_string())
+        .is_err());
+}
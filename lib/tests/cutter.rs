@@ -0,0 +1,79 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use dmi::{reduce_bitmask, CornerPiece, CutterConfig, Dmi, JunctionSet, State};
+
+fn piece_color(piece: CornerPiece) -> Rgba<u8> {
+    Rgba([piece as u8 * 50, 0, 0, 255])
+}
+
+fn template() -> Dmi {
+    let mut dmi = Dmi::new("template".to_string(), 4, 4);
+    for piece in CornerPiece::ALL {
+        let mut state = State::new_blank(piece.state_name().to_string(), 4, 4);
+        state.frames[0] = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, piece_color(piece)));
+        dmi.states.push(state);
+    }
+    dmi
+}
+
+#[test]
+fn junction_sets() {
+    assert_eq!(JunctionSet::Full.bitmasks().len(), 256);
+    assert_eq!(JunctionSet::Reduced.bitmasks().len(), 47);
+
+    // North-east without north is not a junction of the reduced set.
+    assert_eq!(reduce_bitmask(4 | 16), 4);
+    assert_eq!(reduce_bitmask(1 | 4 | 16), 1 | 4 | 16);
+}
+
+#[test]
+fn cut_junctions() {
+    let config = CutterConfig {
+        prefix: "wall".to_string(),
+        ..Default::default()
+    };
+
+    let dmi = template().cut_junctions(&config).unwrap();
+
+    assert_eq!(dmi.states.len(), 47);
+    assert_eq!(dmi.states[0].name, "wall-0");
+
+    let corner = |name: &str, x: u32, y: u32| {
+        let state = dmi.states.iter().find(|state| state.name == name).unwrap();
+        *state.frames[0].to_rgba8().get_pixel(x, y)
+    };
+
+    assert_eq!(corner("wall-0", 0, 0), piece_color(CornerPiece::Convex));
+    assert_eq!(corner("wall-0", 3, 3), piece_color(CornerPiece::Convex));
+
+    // North, east and north-east.
+    assert_eq!(corner("wall-21", 3, 0), piece_color(CornerPiece::Flat));
+    assert_eq!(corner("wall-21", 0, 0), piece_color(CornerPiece::Vertical));
+    assert_eq!(
+        corner("wall-21", 3, 3),
+        piece_color(CornerPiece::Horizontal)
+    );
+    assert_eq!(corner("wall-21", 0, 3), piece_color(CornerPiece::Convex));
+
+    // North and east without the diagonal.
+    assert_eq!(corner("wall-5", 3, 0), piece_color(CornerPiece::Concave));
+}
+
+#[test]
+fn missing_junctions() {
+    let config = CutterConfig {
+        prefix: "table".to_string(),
+        naming: "{prefix}_{bitmask}".to_string(),
+        set: JunctionSet::Reduced,
+    };
+
+    let mut dmi = template().cut_junctions(&config).unwrap();
+    assert!(dmi.missing_junctions(&config).is_empty());
+
+    dmi.states.retain(|state| state.name != "table_15");
+    assert_eq!(dmi.missing_junctions(&config), vec![15]);
+
+    let mut template = template();
+    template.states.remove(0);
+    assert!(template.cut_junctions(&config).is_err());
+}
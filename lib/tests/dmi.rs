@@ -1,7 +1,11 @@
 use std::fs::{remove_dir_all, remove_file};
 use std::path::Path;
 
-use dmi::Dmi;
+use image::{DynamicImage, ImageBuffer, Rgba};
+
+use dmi::{
+    Anchor, Direction, DirectionPreset, DirectionTransform, Dmi, DmiError, Rect, Scope, State,
+};
 
 #[test]
 fn open_and_save() {
@@ -97,3 +101,232 @@ fn serialize_and_deserialize() {
 
     assert_eq!(delay, deserialized_delay);
 }
+
+#[test]
+fn generate_directions() {
+    let mut state = State::new_blank("mob".to_string(), 4, 4);
+    state.set_dirs(4).unwrap();
+
+    let east = &mut state.frames[Direction::East.index() as usize];
+    *east = DynamicImage::ImageRgba8(ImageBuffer::from_fn(4, 4, |x, _| {
+        Rgba([x as u8 * 10, 0, 0, 255])
+    }));
+
+    state
+        .generate_directions(DirectionPreset::MirrorEastToWest)
+        .unwrap();
+
+    let west = state.frames[Direction::West.index() as usize].to_rgba8();
+    assert_eq!(west.get_pixel(0, 0)[0], 30);
+    assert_eq!(west.get_pixel(3, 0)[0], 0);
+
+    state
+        .generate_directions(DirectionPreset::DiagonalsFromCardinal)
+        .unwrap();
+
+    assert_eq!(state.dirs, 8);
+    assert_eq!(state.frames.len(), 8);
+    assert_eq!(
+        state.frames[Direction::NorthEast.index() as usize].to_rgba8(),
+        state.frames[Direction::East.index() as usize].to_rgba8()
+    );
+    assert_eq!(
+        state.frames[Direction::SouthWest.index() as usize].to_rgba8(),
+        west
+    );
+
+    assert!("mirror_north".parse::<DirectionPreset>().is_err());
+}
+
+#[test]
+fn fill_direction_transforms() {
+    let mut state = State::new_blank("mob".to_string(), 3, 3);
+    state.set_dirs(4).unwrap();
+
+    let mut south = ImageBuffer::new(3, 3);
+    south.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+    state.frames[0] = DynamicImage::ImageRgba8(south);
+
+    let marked = |state: &State, direction: Direction| {
+        let image = state.frames[direction.index() as usize].to_rgba8();
+        image
+            .enumerate_pixels()
+            .find(|(_, _, pixel)| pixel[3] > 0)
+            .map(|(x, y, _)| (x, y))
+    };
+
+    let cases = [
+        (Direction::North, DirectionTransform::Rotate90, (2, 0)),
+        (Direction::East, DirectionTransform::Rotate180, (2, 2)),
+        (Direction::West, DirectionTransform::Rotate270, (0, 2)),
+    ];
+    for (target, transform, position) in cases {
+        state
+            .fill_direction(target, Direction::South, transform)
+            .unwrap();
+        assert_eq!(marked(&state, target), Some(position), "{transform:?}");
+    }
+
+    state
+        .fill_direction(
+            Direction::North,
+            Direction::South,
+            DirectionTransform::FlipVertical,
+        )
+        .unwrap();
+    assert_eq!(marked(&state, Direction::North), Some((0, 2)));
+
+    let mut wide = State::new_blank("wide".to_string(), 4, 2);
+    wide.set_dirs(4).unwrap();
+    assert!(matches!(
+        wide.fill_direction(
+            Direction::North,
+            Direction::South,
+            DirectionTransform::Rotate90
+        ),
+        Err(DmiError::NonSquareRotation)
+    ));
+    assert!(wide
+        .fill_direction(
+            Direction::North,
+            Direction::South,
+            DirectionTransform::Rotate180
+        )
+        .is_ok());
+}
+
+#[test]
+fn content_bounds_and_trim() {
+    let bounds_dmi = || {
+        let mut dmi = Dmi::new("bounds".to_string(), 16, 16);
+
+        for (name, x, y) in [("first", 4, 6), ("second", 9, 7)] {
+            let mut state = State::new_blank(name.to_string(), 16, 16);
+            state.frames[0] = DynamicImage::ImageRgba8(ImageBuffer::from_fn(16, 16, |px, py| {
+                if px == x && py == y {
+                    Rgba([255, 255, 255, 255])
+                } else {
+                    Rgba([0, 0, 0, 0])
+                }
+            }));
+            dmi.states.push(state);
+        }
+
+        dmi
+    };
+    let mut dmi = bounds_dmi();
+
+    assert_eq!(
+        dmi.states[0].content_bounds(&Scope::default()),
+        Some(Rect::new(4, 6, 1, 1))
+    );
+    assert_eq!(
+        dmi.content_bounds(&Scope::default()),
+        Some(Rect::new(4, 6, 6, 2))
+    );
+
+    let scope = Scope {
+        states: Some(vec![1]),
+        ..Default::default()
+    };
+    assert_eq!(dmi.content_bounds(&scope), Some(Rect::new(9, 7, 1, 1)));
+
+    let mut scoped = bounds_dmi();
+    assert_eq!(scoped.trim(false, &scope), Some(Rect::new(9, 7, 1, 1)));
+    assert_eq!((scoped.width, scoped.height), (1, 1));
+    assert_eq!(scoped.states[0].frames[0].width(), 1);
+
+    dmi.states[1].hotspots = vec!["9,7,1".to_string(), "1,1,1".to_string()];
+    assert_eq!(
+        dmi.trim(true, &Scope::default()),
+        Some(Rect::new(4, 6, 8, 4))
+    );
+    assert_eq!((dmi.width, dmi.height), (8, 4));
+    assert_eq!(dmi.states[1].frames[0].width(), 8);
+    // The hotspot follows its pixel, the one outside the bounds is dropped.
+    assert_eq!(dmi.states[1].hotspots, vec!["5,1,1".to_string()]);
+
+    assert_eq!(
+        dmi.trim(false, &Scope::default()),
+        Some(Rect::new(0, 0, 6, 2))
+    );
+    assert_eq!((dmi.width, dmi.height), (6, 2));
+
+    let empty = Dmi::new("empty".to_string(), 16, 16);
+    assert_eq!(empty.content_bounds(&Scope::default()), None);
+}
+
+#[test]
+fn resize_canvas() {
+    let mut dmi = Dmi::new("canvas".to_string(), 32, 32);
+    let mut state = State::new_blank("icon".to_string(), 32, 32);
+    state.frames[0] =
+        DynamicImage::ImageRgba8(ImageBuffer::from_pixel(32, 32, Rgba([255, 0, 0, 255])));
+    state.hotspots.push("16,16,1".to_string());
+    dmi.states.push(state);
+
+    assert_eq!(dmi.resize_canvas(64, 64, Anchor::Center), 0);
+
+    let frame = dmi.states[0].frames[0].to_rgba8();
+    assert_eq!((frame.width(), frame.height()), (64, 64));
+    assert_eq!(frame.get_pixel(15, 15)[3], 0);
+    assert_eq!(frame.get_pixel(16, 16)[3], 255);
+    assert_eq!(frame.get_pixel(47, 47)[3], 255);
+    assert_eq!(frame.get_pixel(48, 48)[3], 0);
+    assert_eq!(dmi.states[0].hotspots[0], "32,32,1");
+
+    dmi.states[0].hotspots.push("40,60,1".to_string());
+    assert_eq!(dmi.resize_canvas(40, 20, Anchor::BottomRight), 1);
+
+    let frame = dmi.states[0].frames[0].to_rgba8();
+    assert_eq!((dmi.width, dmi.height), (40, 20));
+    assert_eq!(frame.get_pixel(23, 3)[3], 255);
+    assert_eq!(frame.get_pixel(23, 4)[3], 0);
+    assert_eq!(frame.get_pixel(24, 0)[3], 0);
+    assert_eq!(dmi.states[0].hotspots, vec!["16,16,1".to_string()]);
+
+    assert_eq!(Anchor::Center.offset(5, 5, 2, 2), (-1, -1));
+    assert_eq!(Anchor::Custom(-3, 2).offset(5, 5, 2, 2), (-3, 2));
+    assert!("middle".parse::<Anchor>().is_err());
+}
+
+#[test]
+fn replace_colors() {
+    let red = Rgba([200, 0, 0, 255]);
+    let blue = Rgba([0, 0, 200, 255]);
+
+    let mut dmi = Dmi::new("colors".to_string(), 4, 4);
+    for name in ["first", "second"] {
+        let mut state = State::new_blank(name.to_string(), 4, 4);
+        state.set_dirs(4).unwrap();
+        for frame in state.frames.iter_mut() {
+            *frame = DynamicImage::ImageRgba8(ImageBuffer::from_fn(4, 4, |x, _| {
+                if x == 0 {
+                    Rgba([205, 3, 0, 255])
+                } else {
+                    red
+                }
+            }));
+        }
+        dmi.states.push(state);
+    }
+
+    let map = [(red, blue)];
+
+    let counts = dmi.replace_colors(&map, 0, &Scope::default(), true);
+    assert_eq!(counts, vec![48, 48]);
+    assert_eq!(dmi.states[0].frames[0].to_rgba8().get_pixel(1, 0), &red);
+
+    let scope = Scope {
+        states: Some(vec![1]),
+        dirs: Some(vec![Direction::North]),
+        ..Default::default()
+    };
+    let counts = dmi.replace_colors(&map, 5, &scope, false);
+    assert_eq!(counts, vec![0, 16]);
+
+    let second = &dmi.states[1];
+    assert_eq!(second.frames[1].to_rgba8().get_pixel(0, 0), &blue);
+    assert_eq!(second.frames[0].to_rgba8().get_pixel(1, 0), &red);
+    assert_eq!(dmi.states[0].frames[1].to_rgba8().get_pixel(1, 0), &red);
+}
@@ -0,0 +1,82 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use dmi::{Dmi, DmiError, EmissiveOptions, EmissiveSelector, State};
+
+const CLEAR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const DARK: Rgba<u8> = Rgba([20, 20, 20, 255]);
+const YELLOW: Rgba<u8> = Rgba([255, 230, 0, 200]);
+
+fn row(left: Rgba<u8>, right: Rgba<u8>) -> DynamicImage {
+    let mut image = RgbaImage::from_pixel(2, 1, left);
+    image.put_pixel(1, 0, right);
+    DynamicImage::ImageRgba8(image)
+}
+
+/// A 2 frame, 4 direction state with a yellow light on the left half and dark pixels on the right.
+fn lamp() -> State {
+    let mut state = State::new_blank("lamp".to_string(), 2, 1);
+    let image = row(YELLOW, DARK);
+    state.dirs = 4;
+    state.frame_count = 2;
+    state.frames = vec![image; 8];
+    state.delays = vec![1., 3.];
+    state
+}
+
+#[test]
+fn extract_emissive() {
+    let mut dmi = Dmi::new("lamps".to_string(), 2, 1);
+    dmi.states.push(lamp());
+
+    let index = dmi
+        .extract_emissive(0, &EmissiveOptions::default())
+        .unwrap();
+
+    assert_eq!(index, 1);
+
+    let emissive = &dmi.states[1];
+    assert_eq!(emissive.name, "lamp_emissive");
+    assert_eq!(emissive.dirs, 4);
+    assert_eq!(emissive.frame_count, 2);
+    assert_eq!(emissive.frames.len(), 8);
+    assert_eq!(emissive.delays, vec![1., 3.]);
+
+    let image = emissive.frames[7].to_rgba8();
+    // The alpha of the source is kept.
+    assert_eq!(*image.get_pixel(0, 0), Rgba([255, 255, 255, 200]));
+    assert_eq!(*image.get_pixel(1, 0), CLEAR);
+}
+
+#[test]
+fn selectors() {
+    let state = lamp();
+
+    let colors = EmissiveSelector::Colors {
+        colors: vec![Rgba([25, 25, 25, 255])],
+        tolerance: 5,
+    };
+    let emissive = state.emissive(&colors, None).unwrap();
+    let image = emissive.frames[0].to_rgba8();
+    assert_eq!(*image.get_pixel(0, 0), CLEAR);
+    assert_eq!(*image.get_pixel(1, 0), DARK);
+
+    let mut mask = State::new_blank("mask".to_string(), 2, 1);
+    mask.frames[0] = row(CLEAR, WHITE);
+    let emissive = state
+        .emissive(&EmissiveSelector::Mask(mask), Some(WHITE))
+        .unwrap();
+    let image = emissive.frames[5].to_rgba8();
+    assert_eq!(*image.get_pixel(0, 0), CLEAR);
+    assert_eq!(*image.get_pixel(1, 0), WHITE);
+
+    let wrong_size = State::new_blank("mask".to_string(), 4, 4);
+    assert!(matches!(
+        state.emissive(&EmissiveSelector::Mask(wrong_size), None),
+        Err(DmiError::EmissiveMaskSizeMismatch {
+            mask_width: 4,
+            mask_height: 4,
+            ..
+        })
+    ));
+}
@@ -0,0 +1,162 @@
+use std::fs::{remove_file, write};
+use std::path::{Path, PathBuf};
+
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use dmi::{
+    open_gags_references, parse_gags_colors, Dmi, DmiError, GagsConfig, GagsReferences, State,
+};
+
+const CLEAR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const GREY: Rgba<u8> = Rgba([128, 128, 128, 255]);
+
+/// Writes a greyscale 4x4 icon with a white `base` state and a grey `stripe` on the top row.
+fn greyscale_icon(name: &str) -> PathBuf {
+    let mut dmi = Dmi::new(name.to_string(), 4, 4);
+
+    let mut base = State::new_blank("base".to_string(), 4, 4);
+    base.frames[0] = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, WHITE));
+    dmi.states.push(base);
+
+    let mut stripe = State::new_blank("stripe".to_string(), 4, 4);
+    stripe.frames[0] =
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(
+            4,
+            4,
+            |_, y| if y == 0 { GREY } else { CLEAR },
+        ));
+    dmi.states.push(stripe);
+
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.dmi"));
+    dmi.save(&path).unwrap();
+    path
+}
+
+#[test]
+fn parse_colors() {
+    let colors = parse_gags_colors("#ff0000#00ff0080").unwrap();
+    assert_eq!(colors, vec![Rgba([255, 0, 0, 255]), Rgba([0, 255, 0, 128])]);
+
+    assert!(matches!(
+        parse_gags_colors("#ff00"),
+        Err(DmiError::InvalidHexColor(_))
+    ));
+}
+
+#[test]
+fn render() {
+    let icon = greyscale_icon("gags_render");
+
+    let config = GagsConfig::from_json(
+        r##"{
+            "jumpsuit": [
+                { "type": "icon_state", "icon_state": "base", "blend_mode": "overlay", "color_ids": [1] },
+                [
+                    { "type": "icon_state", "icon_state": "stripe", "blend_mode": "overlay", "color_ids": [2] },
+                    { "type": "color_matrix", "blend_mode": "overlay", "color_matrix": [[0, 0, 1], [0, 1, 0], [1, 0, 0]] }
+                ]
+            ],
+            "plain": [
+                { "type": "icon_state", "icon_state": "base", "blend_mode": "overlay", "color_ids": ["#00ff00"] }
+            ]
+        }"##,
+        &icon,
+    )
+    .unwrap();
+
+    assert_eq!(config.expected_colors(), 2);
+
+    let colors = parse_gags_colors("#ff0000#0000ff").unwrap();
+    let dmi = config.render(&colors, &GagsReferences::new()).unwrap();
+
+    assert_eq!(dmi.states.len(), 2);
+
+    let jumpsuit = &dmi.states[0];
+    assert_eq!(jumpsuit.name, "jumpsuit");
+    let image = jumpsuit.frames[0].to_rgba8();
+    // The blue stripe turns red from the matrix, which leaves the base alone.
+    assert_eq!(*image.get_pixel(0, 0), Rgba([128, 0, 0, 255]));
+    assert_eq!(*image.get_pixel(0, 1), Rgba([255, 0, 0, 255]));
+
+    let plain = &dmi.states[1];
+    assert_eq!(
+        *plain.frames[0].to_rgba8().get_pixel(2, 2),
+        Rgba([0, 255, 0, 255])
+    );
+
+    assert!(matches!(
+        config.render(&colors[..1], &GagsReferences::new()),
+        Err(DmiError::GagsColorCount {
+            expected: 2,
+            got: 1
+        })
+    ));
+
+    let _ = remove_file(icon);
+}
+
+#[test]
+fn references() {
+    let icon = greyscale_icon("gags_references");
+
+    let stripe = GagsConfig::from_json(
+        r#"{ "": [{ "type": "icon_state", "icon_state": "stripe", "blend_mode": "overlay", "color_ids": [1] }] }"#,
+        &icon,
+    )
+    .unwrap();
+
+    let mut references = GagsReferences::new();
+    references.insert("/datum/greyscale_config/stripe".to_string(), stripe);
+
+    let config = GagsConfig::from_json(
+        r#"{
+            "suit": [
+                { "type": "icon_state", "icon_state": "base", "blend_mode": "overlay" },
+                { "type": "reference", "reference_type": "/datum/greyscale_config/stripe", "blend_mode": "multiply", "color_ids": [1] }
+            ]
+        }"#,
+        &icon,
+    )
+    .unwrap();
+
+    let colors = parse_gags_colors("#0000ff").unwrap();
+    let state = config.render_state("suit", &colors, &references).unwrap();
+    let image = state.frames[0].to_rgba8();
+
+    assert_eq!(*image.get_pixel(0, 0), Rgba([0, 0, 128, 255]));
+    // Multiplying by the transparent part of the stripe clears the base.
+    assert_eq!(*image.get_pixel(0, 1), CLEAR);
+
+    assert!(matches!(
+        config.render_state("suit", &colors, &GagsReferences::new()),
+        Err(DmiError::Gags { .. })
+    ));
+
+    let temp = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let stripe_config = temp.join("gags_references_stripe.json");
+    write(
+        &stripe_config,
+        r#"{ "": [{ "type": "icon_state", "icon_state": "stripe", "blend_mode": "overlay", "color_ids": [1] }] }"#,
+    )
+    .unwrap();
+    let references_file = temp.join("gags_references.json");
+    write(
+        &references_file,
+        r#"{
+            "/datum/greyscale_config/stripe": {
+                "config": "gags_references_stripe.json",
+                "icon": "gags_references.dmi"
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let references = open_gags_references(&references_file).unwrap();
+    let state = config.render_state("suit", &colors, &references).unwrap();
+    assert_eq!(state.frames[0].to_rgba8(), image);
+
+    let _ = remove_file(stripe_config);
+    let _ = remove_file(references_file);
+    let _ = remove_file(icon);
+}
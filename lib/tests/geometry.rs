@@ -0,0 +1,117 @@
+use image::{DynamicImage, ImageBuffer, Rgba, RgbaImage};
+
+use dmi::{scale_image, shift_image, turn_image, Direction, Dmi, GeometryMode, Scope, State};
+
+const CLEAR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+fn dot(x: u32, y: u32) -> RgbaImage {
+    ImageBuffer::from_fn(4, 4, |px, py| if (px, py) == (x, y) { RED } else { CLEAR })
+}
+
+#[test]
+fn shift() {
+    let shifted = shift_image(&dot(3, 0), Direction::East, 1, false);
+    assert!(shifted.pixels().all(|pixel| *pixel == CLEAR));
+
+    let wrapped = shift_image(&dot(3, 0), Direction::East, 1, true);
+    assert_eq!(*wrapped.get_pixel(0, 0), RED);
+
+    let north = shift_image(&dot(1, 2), Direction::North, 2, false);
+    assert_eq!(*north.get_pixel(1, 0), RED);
+
+    let diagonal = shift_image(&dot(1, 1), Direction::SouthEast, 1, false);
+    assert_eq!(*diagonal.get_pixel(2, 2), RED);
+}
+
+#[test]
+fn turn() {
+    let turned = turn_image(&dot(0, 0), 90.);
+    assert_eq!(*turned.get_pixel(3, 0), RED);
+
+    let turned = turn_image(&dot(0, 0), -90.);
+    assert_eq!(*turned.get_pixel(0, 3), RED);
+
+    // Nearest-neighbor keeps the palette intact for other angles.
+    let image = RgbaImage::from_pixel(8, 8, RED);
+    let turned = turn_image(&image, 45.);
+    assert!(turned
+        .pixels()
+        .all(|pixel| *pixel == RED || *pixel == CLEAR));
+    assert_eq!(*turned.get_pixel(4, 4), RED);
+    assert_eq!(*turned.get_pixel(0, 0), CLEAR);
+
+    let mut state = State::new_blank("dirs".to_string(), 4, 4);
+    state.set_dirs(4).unwrap();
+    state.frames[Direction::South.index() as usize] = DynamicImage::ImageRgba8(dot(0, 0));
+
+    let mut simple = state.clone();
+    simple.turn(90., GeometryMode::Simple);
+    assert_eq!(
+        *simple.frames[Direction::South.index() as usize]
+            .to_rgba8()
+            .get_pixel(3, 0),
+        RED
+    );
+
+    state.turn(90., GeometryMode::Byond);
+    assert_eq!(
+        *state.frames[Direction::West.index() as usize]
+            .to_rgba8()
+            .get_pixel(3, 0),
+        RED
+    );
+    assert!(state.frames[Direction::South.index() as usize]
+        .to_rgba8()
+        .pixels()
+        .all(|pixel| *pixel == CLEAR));
+}
+
+#[test]
+fn flip() {
+    let mut state = State::new_blank("dirs".to_string(), 4, 4);
+    state.set_dirs(8).unwrap();
+    state.frames[Direction::NorthEast.index() as usize] = DynamicImage::ImageRgba8(dot(0, 1));
+
+    state.flip(Direction::East, GeometryMode::Byond).unwrap();
+    assert_eq!(
+        *state.frames[Direction::NorthWest.index() as usize]
+            .to_rgba8()
+            .get_pixel(3, 1),
+        RED
+    );
+
+    assert!(state
+        .flip(Direction::NorthEast, GeometryMode::Byond)
+        .is_err());
+}
+
+#[test]
+fn scale() {
+    let image = ImageBuffer::from_fn(4, 4, |x, _| if x < 2 { RED } else { CLEAR });
+
+    let smaller = scale_image(&image, 2, 2);
+    assert_eq!(*smaller.get_pixel(0, 0), RED);
+    assert_eq!(*smaller.get_pixel(1, 0), CLEAR);
+
+    let half = scale_image(&image, 1, 1);
+    assert_eq!(*half.get_pixel(0, 0), Rgba([255, 0, 0, 128]));
+
+    let mut dmi = Dmi::new("scale".to_string(), 4, 4);
+    let mut state = State::new_blank("icon".to_string(), 4, 4);
+    state.frames[0] = DynamicImage::ImageRgba8(image);
+    state.hotspots = vec!["1,3,1".to_string()];
+    dmi.states.push(state);
+
+    dmi.scale(8, 8, GeometryMode::Byond);
+    assert_eq!((dmi.width, dmi.height), (8, 8));
+    assert_eq!(*dmi.states[0].frames[0].to_rgba8().get_pixel(3, 7), RED);
+    assert_eq!(dmi.states[0].hotspots, vec!["2,6,1".to_string()]);
+
+    dmi.scale(2, 2, GeometryMode::Simple);
+    assert_eq!(dmi.states[0].hotspots, vec!["0,1,1".to_string()]);
+    dmi.scale(8, 8, GeometryMode::Simple);
+
+    dmi.shift(Direction::West, 4, true, &Scope::default());
+    assert_eq!(*dmi.states[0].frames[0].to_rgba8().get_pixel(7, 0), RED);
+}
@@ -0,0 +1,240 @@
+use std::path::Path;
+
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use dmi::{
+    lint_dir, lint_palette_dir, Dmi, LintIssue, LintOptions, PaletteViolation, SaveOptions, State,
+};
+
+const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+fn palette() -> Vec<Rgba<u8>> {
+    vec![RED, BLUE]
+}
+
+/// Two dirs and two frames, the second frame of the second dir has a dark red pixel at 1,0.
+fn state(name: &str) -> State {
+    let mut state = State::new_blank(name.to_string(), 2, 2);
+    state.dirs = 2;
+    state.frame_count = 2;
+    state.delays = vec![1., 1.];
+    state.frames = (0..4)
+        .map(|index| {
+            DynamicImage::ImageRgba8(RgbaImage::from_fn(2, 2, |x, y| match (index, x, y) {
+                (3, 1, 0) => Rgba([200, 10, 0, 255]),
+                (_, 0, 0) => Rgba([9, 9, 9, 0]),
+                (_, _, 1) => BLUE,
+                _ => RED,
+            }))
+        })
+        .collect();
+    state
+}
+
+fn icon() -> Dmi {
+    let mut dmi = Dmi::new("icon".to_string(), 2, 2);
+    dmi.states.push(state("clean"));
+    dmi.states[0].frames[3] = dmi.states[0].frames[0].clone();
+    dmi.states.push(state("dirty"));
+    dmi
+}
+
+#[test]
+fn palette_violations() {
+    let dmi = icon();
+
+    assert_eq!(
+        dmi.palette_violations(&palette(), 0),
+        vec![PaletteViolation {
+            index: 1,
+            state: "dirty".to_string(),
+            dir: 1,
+            frame: 1,
+            x: 1,
+            y: 0,
+            color: Rgba([200, 10, 0, 255]),
+        }]
+    );
+    assert!(dmi.palette_violations(&palette(), 60).is_empty());
+}
+
+#[test]
+fn snap_to_palette() {
+    let mut dmi = icon();
+
+    assert_eq!(dmi.snap_to_palette(&palette(), 0), 1);
+    assert!(dmi.palette_violations(&palette(), 0).is_empty());
+
+    let frame = dmi.states[1].frames[3].to_rgba8();
+    assert_eq!(*frame.get_pixel(1, 0), RED);
+    // Transparent pixels are never off-palette.
+    assert_eq!(*frame.get_pixel(0, 0), Rgba([9, 9, 9, 0]));
+}
+
+#[test]
+fn lint_directory() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("dmi_lint_test");
+    std::fs::create_dir_all(dir.join("nested")).unwrap();
+
+    let mut clean = icon();
+    clean.states.remove(1);
+    clean.save(dir.join("clean.dmi")).unwrap();
+    icon().save(dir.join("nested").join("dirty.dmi")).unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a dmi").unwrap();
+    std::fs::write(dir.join("broken.dmi"), "not a dmi").unwrap();
+
+    let results = lint_palette_dir(&dir, &palette(), 0).unwrap();
+
+    assert_eq!(results.len(), 2);
+    // A file that fails to open is reported without stopping the walk.
+    assert_eq!(results[0].0, dir.join("broken.dmi"));
+    assert!(results[0].1.is_err());
+    assert_eq!(results[1].0, dir.join("nested").join("dirty.dmi"));
+    let violations = results[1].1.as_ref().unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].state, "dirty");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn alpha_hygiene() {
+    let mut dmi = icon();
+    dmi.states[0].frames[1] =
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, Rgba([1, 2, 3, 128])));
+
+    let options = LintOptions {
+        forbid_partial_alpha: true,
+        transparent_rgb: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        dmi.lint(&options),
+        vec![
+            LintIssue::PartialAlpha {
+                index: 0,
+                state: "clean".to_string(),
+                pixels: 4,
+            },
+            LintIssue::TransparentRgb {
+                index: 0,
+                state: "clean".to_string(),
+                pixels: 3,
+            },
+            LintIssue::TransparentRgb {
+                index: 1,
+                state: "dirty".to_string(),
+                pixels: 4,
+            },
+        ]
+    );
+    assert!(dmi.lint(&LintOptions::default()).is_empty());
+
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("dmi_alpha_test");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    dmi.save(dir.join("kept.dmi")).unwrap();
+    dmi.save_with(
+        dir.join("cleared.dmi"),
+        &SaveOptions {
+            clear_transparent: true,
+        },
+    )
+    .unwrap();
+
+    let kept = Dmi::open(dir.join("kept.dmi")).unwrap();
+    let cleared = Dmi::open(dir.join("cleared.dmi")).unwrap();
+    assert_eq!(kept.states[1].transparent_rgb_pixels(), 4);
+    assert_eq!(cleared.states[1].transparent_rgb_pixels(), 0);
+    assert_eq!(
+        *cleared.states[1].frames[0].to_rgba8().get_pixel(0, 0),
+        Rgba([0, 0, 0, 0])
+    );
+
+    let results = lint_dir(&dir, &options).unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].0, dir.join("cleared.dmi"));
+    assert_eq!(results[0].1.as_ref().unwrap().len(), 1);
+    assert_eq!(results[1].1.as_ref().unwrap().len(), 3);
+
+    std::fs::remove_dir_all(dir).unwrap();
+
+    assert_eq!(dmi.clear_transparent(), 7);
+    assert_eq!(dmi.clear_transparent(), 0);
+}
+
+fn solid(value: u8) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, Rgba([value, 0, 0, 255])))
+}
+
+/// An 8 direction state, each frame holds the given values in dmi direction order.
+fn directional(name: &str, frames: &[[u8; 8]]) -> State {
+    let mut state = State::new_blank(name.to_string(), 2, 2);
+    state.dirs = 8;
+    state.frame_count = frames.len() as u32;
+    state.delays = vec![1.; frames.len()];
+    state.frames = frames.iter().flatten().map(|value| solid(*value)).collect();
+    state
+}
+
+#[test]
+fn collapse_dirs() {
+    // S, N, E, W, SE, SW, NE, NW with the diagonals falling back to east and west.
+    let mut diagonal = directional(
+        "diagonal",
+        &[[1, 2, 3, 4, 3, 4, 3, 4], [5, 6, 7, 8, 7, 8, 7, 8]],
+    );
+    diagonal.hotspots = vec!["1,1,10".to_string()];
+    assert_eq!(diagonal.collapsible_dirs(), Some(4));
+    assert_eq!(diagonal.collapse_dirs(), Some(4));
+    assert_eq!(diagonal.frames.len(), 8);
+    assert_eq!(diagonal.frames[6].to_rgba8().get_pixel(0, 0)[0], 7);
+    assert_eq!(diagonal.hotspots, vec!["1,1,6".to_string()]);
+
+    let mut uniform = directional("uniform", &[[1; 8]]);
+    uniform.set_dirs(4).unwrap();
+    assert_eq!(uniform.collapse_dirs(), Some(1));
+    assert_eq!(uniform.frames.len(), 1);
+
+    // A hotspot on one direction only keeps the directions apart.
+    let mut marked = directional("marked", &[[1; 8]]);
+    marked.hotspots = vec!["0,0,8".to_string()];
+    assert_eq!(marked.collapsible_dirs(), None);
+    marked.hotspots = vec![
+        "0,0,4".to_string(),
+        "0,0,6".to_string(),
+        "0,0,8".to_string(),
+    ];
+    assert_eq!(marked.collapsible_dirs(), Some(4));
+
+    let distinct = directional("distinct", &[[1, 2, 3, 4, 5, 6, 7, 8]]);
+    assert_eq!(distinct.collapsible_dirs(), None);
+
+    let mut dmi = Dmi::new("icon".to_string(), 2, 2);
+    dmi.states.push(distinct);
+    dmi.states
+        .push(directional("same", &[[1; 8], [2; 8], [3; 8]]));
+
+    assert_eq!(
+        dmi.lint(&LintOptions {
+            redundant_dirs: true,
+            ..Default::default()
+        }),
+        vec![LintIssue::RedundantDirs {
+            index: 1,
+            state: "same".to_string(),
+            dirs: 8,
+            collapsible_to: 1,
+        }]
+    );
+
+    let report = dmi.collapse_dirs().unwrap();
+    assert_eq!(report.states, vec![(1, 8, 1)]);
+    assert!(report.bytes_saved() > 0);
+    assert_eq!(dmi.states[1].frames.len(), 3);
+
+    let report = dmi.collapse_dirs().unwrap();
+    assert!(report.states.is_empty());
+    assert_eq!(report.bytes_saved(), 0);
+}
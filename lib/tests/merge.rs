@@ -0,0 +1,117 @@
+use dmi::{Anchor, ConflictPolicy, Dmi, DmiError, MergePolicy, ResizeMethod, SizePolicy, State};
+
+fn dmi(size: u32, states: &[(&str, bool)]) -> Dmi {
+    let mut dmi = Dmi::new("icon".to_string(), size, size);
+    for (name, movement) in states {
+        let mut state = State::new_blank(name.to_string(), size, size);
+        state.movement = *movement;
+        dmi.states.push(state);
+    }
+    dmi
+}
+
+fn names(dmi: &Dmi) -> Vec<(&str, bool)> {
+    dmi.states
+        .iter()
+        .map(|state| (state.name.as_str(), state.movement))
+        .collect()
+}
+
+#[test]
+fn conflicts() {
+    let base = dmi(32, &[("walk", false), ("walk", true), ("idle", false)]);
+    let other = dmi(32, &[("walk", false), ("walk", true), ("run", true)]);
+
+    let mut merged = dmi(32, &[]);
+    merged.merge(base, &MergePolicy::default()).unwrap();
+    let report = merged.merge(other, &MergePolicy::default()).unwrap();
+
+    assert_eq!(
+        names(&merged),
+        vec![
+            ("walk", false),
+            ("walk", true),
+            ("idle", false),
+            ("walk 2", false),
+            ("walk 2", true),
+            ("run", true),
+        ]
+    );
+    assert_eq!(
+        report.renamed,
+        vec![("walk".to_string(), "walk 2".to_string())]
+    );
+
+    let policy = |conflicts| MergePolicy {
+        conflicts,
+        ..Default::default()
+    };
+
+    let mut replaced = dmi(32, &[("walk", false), ("idle", false)]);
+    let report = replaced
+        .merge(
+            dmi(32, &[("walk", true), ("idle", false)]),
+            &policy(ConflictPolicy::Replace),
+        )
+        .unwrap();
+    assert_eq!(
+        names(&replaced),
+        vec![("walk", false), ("idle", false), ("walk", true)]
+    );
+    assert_eq!(report.replaced, vec!["idle".to_string()]);
+
+    let mut skipped = dmi(32, &[("idle", false)]);
+    let report = skipped
+        .merge(dmi(32, &[("idle", false)]), &policy(ConflictPolicy::Skip))
+        .unwrap();
+    assert_eq!(skipped.states.len(), 1);
+    assert_eq!(report.skipped, vec!["idle".to_string()]);
+
+    let mut failed = dmi(32, &[("idle", false)]);
+    assert!(matches!(
+        failed.merge(
+            dmi(32, &[("new", false), ("idle", false)]),
+            &policy(ConflictPolicy::Error)
+        ),
+        Err(DmiError::StateConflict(_))
+    ));
+    assert_eq!(failed.states.len(), 1);
+}
+
+#[test]
+fn sizes() {
+    let mut rejected = dmi(32, &[("a", false)]);
+    assert!(matches!(
+        rejected.merge(dmi(64, &[("b", false)]), &MergePolicy::default()),
+        Err(DmiError::DmiSizeMismatch { .. })
+    ));
+
+    let mut expanded = dmi(32, &[("a", false)]);
+    expanded
+        .merge(
+            dmi(64, &[("b", false)]),
+            &MergePolicy {
+                conflicts: ConflictPolicy::Rename,
+                size: SizePolicy::Expand(Anchor::Center),
+            },
+        )
+        .unwrap();
+    assert_eq!((expanded.width, expanded.height), (64, 64));
+    assert!(expanded
+        .states
+        .iter()
+        .all(|state| state.frames[0].width() == 64));
+
+    let mut scaled = dmi(32, &[("a", false)]);
+    scaled
+        .merge(
+            dmi(64, &[("b", false)]),
+            &MergePolicy {
+                conflicts: ConflictPolicy::Rename,
+                size: SizePolicy::Scale("nearest".parse::<ResizeMethod>().unwrap()),
+            },
+        )
+        .unwrap();
+    assert_eq!((scaled.width, scaled.height), (32, 32));
+    assert_eq!(scaled.states[1].frames[0].width(), 32);
+}
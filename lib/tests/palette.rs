@@ -0,0 +1,107 @@
+use std::path::Path;
+
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use dmi::{
+    decode_palette, encode_palette, open_palette, save_palette, Dmi, DmiError, PaletteFormat, State,
+};
+
+fn colors() -> Vec<Rgba<u8>> {
+    vec![
+        Rgba([255, 0, 0, 255]),
+        Rgba([0, 128, 255, 255]),
+        Rgba([12, 34, 56, 255]),
+    ]
+}
+
+#[test]
+fn extract() {
+    let mut state = State::new_blank("state".to_string(), 4, 1);
+    state.frames = vec![DynamicImage::ImageRgba8(RgbaImage::from_fn(
+        4,
+        1,
+        |x, _| match x {
+            0 => Rgba([1, 2, 3, 0]),
+            1 => Rgba([0, 0, 255, 255]),
+            _ => Rgba([255, 0, 0, 255]),
+        },
+    ))];
+
+    let mut dmi = Dmi::new("icon".to_string(), 4, 1);
+    dmi.states.push(state.clone());
+    dmi.states.push(state);
+
+    assert_eq!(
+        dmi.palette(),
+        vec![(Rgba([255, 0, 0, 255]), 4), (Rgba([0, 0, 255, 255]), 2)]
+    );
+    assert_eq!(dmi.states[0].palette()[1], (Rgba([0, 0, 255, 255]), 1));
+}
+
+#[test]
+fn round_trip() {
+    for format in [PaletteFormat::Gpl, PaletteFormat::Pal, PaletteFormat::Act] {
+        let bytes = encode_palette(&colors(), format, "test").unwrap();
+        assert_eq!(decode_palette(&bytes, format).unwrap(), colors());
+    }
+
+    let mut translucent = colors();
+    translucent.push(Rgba([1, 2, 3, 128]));
+    let bytes = encode_palette(&translucent, PaletteFormat::Gpl, "test").unwrap();
+    assert!(String::from_utf8_lossy(&bytes).contains("Channels: RGBA"));
+    assert_eq!(
+        decode_palette(&bytes, PaletteFormat::Gpl).unwrap(),
+        translucent
+    );
+}
+
+#[test]
+fn read_files() {
+    let gpl =
+        "GIMP Palette\nName: Test\nColumns: 4\n# comment\n255   0   0\tRed\n  0 128 255 Blue\n";
+    assert_eq!(
+        decode_palette(gpl.as_bytes(), PaletteFormat::Gpl).unwrap(),
+        colors()[..2]
+    );
+
+    let pal = "JASC-PAL\r\n0100\r\n2\r\n255 0 0\r\n0 128 255\r\n";
+    assert_eq!(
+        decode_palette(pal.as_bytes(), PaletteFormat::Pal).unwrap(),
+        colors()[..2]
+    );
+
+    // Color tables without the count hold 256 colors.
+    let act = vec![7; 768];
+    assert_eq!(
+        decode_palette(&act, PaletteFormat::Act).unwrap(),
+        vec![Rgba([7, 7, 7, 255]); 256]
+    );
+
+    assert!(matches!(
+        decode_palette(b"JASC-PAL\n0100\n3\n1 2 3\n", PaletteFormat::Pal),
+        Err(DmiError::InvalidPalette(_))
+    ));
+    assert!(matches!(
+        encode_palette(&vec![Rgba([0, 0, 0, 255]); 257], PaletteFormat::Act, "test"),
+        Err(DmiError::PaletteTooLarge(257))
+    ));
+}
+
+#[test]
+fn files() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("dmi_palette_test");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    for extension in ["gpl", "pal", "act"] {
+        let path = dir.join(format!("palette.{extension}"));
+        save_palette(&path, &colors()).unwrap();
+        assert_eq!(open_palette(&path).unwrap(), colors());
+    }
+
+    assert!(matches!(
+        save_palette(dir.join("palette.txt"), &colors()),
+        Err(DmiError::UnknownPaletteFormat(_))
+    ));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
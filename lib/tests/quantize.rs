@@ -0,0 +1,121 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use dmi::{color_distance, Dmi, DmiError, QuantizeMethod, QuantizeOptions, QuantizeTarget, State};
+
+/// Soft red and blue brush strokes on a transparent background.
+fn soft_state(name: &str) -> State {
+    let mut state = State::new_blank(name.to_string(), 8, 8);
+    state.frames = vec![DynamicImage::ImageRgba8(RgbaImage::from_fn(
+        8,
+        8,
+        |x, y| match y {
+            0..=2 => Rgba([200 + x as u8, 10 + y as u8, 10, 255]),
+            3..=5 => Rgba([10, 10 + x as u8, 200 + y as u8, 255]),
+            _ => Rgba([x as u8, 0, 0, 0]),
+        },
+    ))];
+    state
+}
+
+fn colors(state: &State) -> Vec<Rgba<u8>> {
+    let mut colors = state.frames[0]
+        .to_rgba8()
+        .pixels()
+        .filter(|pixel| pixel[3] > 0)
+        .copied()
+        .collect::<Vec<_>>();
+    colors.sort_by_key(|color| color.0);
+    colors.dedup();
+    colors
+}
+
+#[test]
+fn distance() {
+    let red = Rgba([255, 0, 0, 255]);
+    assert_eq!(color_distance(red, red), 0);
+    assert!(
+        color_distance(Rgba([255, 0, 0, 10]), Rgba([0, 0, 255, 10]))
+            < color_distance(Rgba([255, 0, 0, 10]), red)
+    );
+}
+
+#[test]
+fn reduce_colors() {
+    for method in [QuantizeMethod::MedianCut, QuantizeMethod::KMeans(8)] {
+        let mut state = soft_state("soft");
+        let report = state
+            .quantize(&QuantizeOptions {
+                target: QuantizeTarget::Colors(2),
+                method,
+                dither: false,
+            })
+            .unwrap();
+
+        assert_eq!(report.colors_before, 48);
+        assert_eq!(report.colors_after, 2);
+
+        let colors = colors(&state);
+        assert_eq!(colors.len(), 2);
+        assert!(colors.iter().any(|color| color[0] > 150 && color[2] < 50));
+        assert!(colors.iter().any(|color| color[2] > 150 && color[0] < 50));
+
+        // Transparent pixels are left alone.
+        assert_eq!(
+            *state.frames[0].to_rgba8().get_pixel(3, 7),
+            Rgba([3, 0, 0, 0])
+        );
+    }
+}
+
+#[test]
+fn fixed_palette() {
+    let palette = vec![Rgba([0, 0, 255, 255]), Rgba([255, 0, 0, 255])];
+
+    let mut dmi = Dmi::new("icon".to_string(), 8, 8);
+    dmi.states.push(soft_state("a"));
+    dmi.states.push(soft_state("b"));
+
+    let report = dmi
+        .quantize(&QuantizeOptions {
+            target: QuantizeTarget::Palette(palette.clone()),
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert_eq!(report.colors_after, 2);
+    assert_eq!(report.palette, palette);
+    assert_eq!(colors(&dmi.states[1]), palette);
+
+    let mut dithered = soft_state("soft");
+    dithered
+        .quantize(&QuantizeOptions {
+            target: QuantizeTarget::Palette(palette.clone()),
+            dither: true,
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(colors(&dithered), palette);
+
+    assert!(matches!(
+        dmi.quantize(&QuantizeOptions {
+            target: QuantizeTarget::Colors(0),
+            ..Default::default()
+        }),
+        Err(DmiError::EmptyPalette)
+    ));
+}
+
+#[test]
+fn few_colors_unchanged() {
+    let mut state = soft_state("soft");
+    let original = state.frames[0].to_rgba8();
+    let report = state
+        .quantize(&QuantizeOptions {
+            target: QuantizeTarget::Colors(64),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(report.colors_before, 48);
+    assert_eq!(report.colors_after, 48);
+    assert_eq!(state.frames[0].to_rgba8(), original);
+}
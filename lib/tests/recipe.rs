@@ -0,0 +1,207 @@
+use std::fs::{create_dir_all, remove_dir_all, write};
+use std::path::Path;
+
+use image::{Rgba, RgbaImage};
+
+use dmi::{Dmi, DmiError};
+
+fn build(name: &str, recipe: &str) -> Result<Dmi, DmiError> {
+    let temp_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    create_dir_all(&temp_dir).unwrap();
+
+    RgbaImage::from_pixel(32, 32, Rgba([255, 255, 255, 255]))
+        .save(temp_dir.join("white.png"))
+        .unwrap();
+
+    let anomaly = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/assets/anomaly.dmi");
+    let recipe = recipe.replace("$ANOMALY", &anomaly.to_string_lossy().replace('\\', "/"));
+    write(temp_dir.join("recipe.json"), recipe).unwrap();
+
+    let result = Dmi::build_from_recipe(temp_dir.join("recipe.json"));
+
+    let _ = remove_dir_all(temp_dir);
+
+    result
+}
+
+#[test]
+fn build_from_recipe() {
+    let dmi = build(
+        "recipe_build",
+        r#"{
+            "name": "built",
+            "width": 32,
+            "height": 32,
+            "inputs": {
+                "rift": { "dmi": "$ANOMALY", "state": "carp_rift" },
+                "white": { "png": "white.png" },
+                "red": { "matrix": [1, 0, 0, 0, 0, 0, 0, 0, 0] }
+            },
+            "states": [
+                {
+                    "name": "red",
+                    "from": "white",
+                    "steps": [
+                        { "op": "color_matrix", "input": "red" },
+                        { "op": "set", "dirs": 4, "movement": true }
+                    ]
+                },
+                {
+                    "name": "rift",
+                    "from": "rift",
+                    "steps": [{ "op": "shift", "dir": "east", "offset": 2 }]
+                },
+                {
+                    "name": "blank",
+                    "steps": [{ "op": "blend_color", "color": [0, 0, 255], "mode": "or" }]
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(dmi.name, "built");
+    assert_eq!(dmi.states.len(), 3);
+
+    let red = &dmi.states[0];
+    assert_eq!(red.dirs, 4);
+    assert!(red.movement);
+    assert_eq!(
+        *red.frames[0].to_rgba8().get_pixel(0, 0),
+        Rgba([255, 0, 0, 255])
+    );
+
+    let rift = &dmi.states[1];
+    assert_eq!(rift.frame_count, 3);
+    assert_eq!(rift.delays, vec![2.0; 3]);
+
+    let blank = &dmi.states[2];
+    assert_eq!(
+        *blank.frames[0].to_rgba8().get_pixel(0, 0),
+        Rgba([0, 0, 255, 255])
+    );
+}
+
+#[test]
+fn recipe_errors() {
+    let error = build(
+        "recipe_step_error",
+        r#"{
+            "name": "broken",
+            "width": 32,
+            "height": 32,
+            "states": [
+                {
+                    "name": "flipped",
+                    "steps": [
+                        { "op": "shift", "dir": "north", "offset": 1 },
+                        { "op": "flip", "dir": "northeast" }
+                    ]
+                }
+            ]
+        }"#,
+    )
+    .unwrap_err();
+
+    assert!(matches!(
+        error,
+        DmiError::RecipeStep {
+            step: 2,
+            op: "flip",
+            ..
+        }
+    ));
+
+    let error = build(
+        "recipe_missing_input",
+        r#"{
+            "name": "broken",
+            "width": 32,
+            "height": 32,
+            "states": [{ "name": "missing", "from": "nothing" }]
+        }"#,
+    )
+    .unwrap_err();
+
+    assert!(matches!(error, DmiError::MissingRecipeInput(_)));
+
+    let error = build(
+        "recipe_size_mismatch",
+        r#"{
+            "name": "broken",
+            "width": 32,
+            "height": 32,
+            "states": [
+                {
+                    "name": "small",
+                    "steps": [{ "op": "resize", "width": 16, "height": 16, "method": "nearest" }]
+                }
+            ]
+        }"#,
+    )
+    .unwrap_err();
+
+    assert!(matches!(error, DmiError::RecipeSizeMismatch { .. }));
+
+    let error = build(
+        "recipe_missing_matrix",
+        r#"{
+            "name": "broken",
+            "width": 32,
+            "height": 32,
+            "states": [{ "name": "plain", "steps": [{ "op": "color_matrix" }] }]
+        }"#,
+    )
+    .unwrap_err();
+
+    let DmiError::RecipeStep { op, source, .. } = error else {
+        panic!("{error}");
+    };
+    assert_eq!(op, "color_matrix");
+    assert!(matches!(*source, DmiError::MissingColorMatrix));
+
+    let error = build(
+        "recipe_delay_count",
+        r#"{
+            "name": "broken",
+            "width": 32,
+            "height": 32,
+            "states": [{ "name": "plain", "steps": [{ "op": "set", "delays": [1, 2] }] }]
+        }"#,
+    )
+    .unwrap_err();
+
+    assert!(error.to_string().contains("Expected 1 delays"));
+    let DmiError::RecipeStep { source, .. } = error else {
+        panic!("{error}");
+    };
+    assert!(matches!(
+        *source,
+        DmiError::DelayCountMismatch {
+            expected: 1,
+            got: 2
+        }
+    ));
+
+    // With several bad inputs the first by name is reported every time.
+    for _ in 0..4 {
+        let error = build(
+            "recipe_bad_inputs",
+            r#"{
+                "name": "broken",
+                "width": 32,
+                "height": 32,
+                "inputs": {
+                    "d": { "png": "missing_d.png" },
+                    "b": { "png": "missing_b.png" },
+                    "c": { "matrix": [1, 2] },
+                    "a_ok": { "png": "white.png" }
+                },
+                "states": []
+            }"#,
+        )
+        .unwrap_err();
+
+        assert!(matches!(error, DmiError::RecipeInput { input, .. } if input == "b"));
+    }
+}
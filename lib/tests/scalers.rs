@@ -0,0 +1,89 @@
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageBuffer, Rgba, RgbaImage};
+
+use dmi::{scale2x, scale3x, xbr2x, PixelScaler, ResizeMethod};
+
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+fn diagonal() -> RgbaImage {
+    ImageBuffer::from_fn(3, 3, |x, y| if x <= y { BLACK } else { WHITE })
+}
+
+#[test]
+fn scale2x_smooths_diagonals() {
+    let image = scale2x(&diagonal());
+
+    assert_eq!((image.width(), image.height()), (6, 6));
+    // The white pixel above the diagonal loses its bottom-left corner to the black edge.
+    assert_eq!(*image.get_pixel(2, 0), WHITE);
+    assert_eq!(*image.get_pixel(3, 0), WHITE);
+    assert_eq!(*image.get_pixel(2, 1), BLACK);
+    assert_eq!(*image.get_pixel(3, 1), WHITE);
+
+    let flat = ImageBuffer::from_pixel(4, 4, WHITE);
+    assert!(scale2x(&flat).pixels().all(|pixel| *pixel == WHITE));
+}
+
+#[test]
+fn scale3x_and_xbr_keep_flat_areas() {
+    let flat = ImageBuffer::from_pixel(4, 4, BLACK);
+
+    assert!(scale3x(&flat).pixels().all(|pixel| *pixel == BLACK));
+    assert!(xbr2x(&flat).pixels().all(|pixel| *pixel == BLACK));
+
+    let image = xbr2x(&diagonal());
+    assert_eq!((image.width(), image.height()), (6, 6));
+    assert_eq!(*image.get_pixel(0, 5), BLACK);
+    assert_eq!(*image.get_pixel(5, 0), WHITE);
+}
+
+#[test]
+fn resize_to_target_size() {
+    let image = DynamicImage::ImageRgba8(diagonal());
+    let twice = scale2x(&scale2x(&diagonal()));
+
+    for (scaler, size, expected) in [
+        (PixelScaler::Scale2x, 3, diagonal()),
+        (PixelScaler::Scale2x, 12, twice.clone()),
+        (PixelScaler::Scale3x, 9, scale3x(&diagonal())),
+        (PixelScaler::Scale4x, 12, twice),
+        (PixelScaler::Xbr2x, 6, xbr2x(&diagonal())),
+    ] {
+        let resized = scaler.resize(&image, size, size).unwrap();
+        assert_eq!(resized.to_rgba8(), expected, "{scaler:?} to {size}");
+    }
+
+    for (scaler, width, height) in [
+        (PixelScaler::Scale3x, 8, 8),
+        (PixelScaler::Xbr2x, 9, 9),
+        (PixelScaler::Scale2x, 12, 6),
+        (PixelScaler::Scale2x, 0, 0),
+        (PixelScaler::Scale4x, 6, 6),
+    ] {
+        assert!(scaler.resize(&image, width, height).is_err(), "{scaler:?}");
+    }
+
+    let empty = DynamicImage::new_rgba8(0, 0);
+    assert!(PixelScaler::Scale2x.resize(&empty, 4, 4).is_err());
+    assert_eq!(PixelScaler::Scale3x.steps(2, 1, 18, 9), Some(2));
+}
+
+#[test]
+fn parse_resize_method() {
+    assert_eq!(
+        "xbr2x".parse::<ResizeMethod>().unwrap(),
+        ResizeMethod::PixelArt(PixelScaler::Xbr2x)
+    );
+    assert_eq!(
+        "nearest".parse::<ResizeMethod>().unwrap(),
+        ResizeMethod::Filter(FilterType::Nearest)
+    );
+
+    let error = "bicubic".parse::<ResizeMethod>().unwrap_err().to_string();
+    assert!(error.contains("\"bicubic\""));
+    for name in ResizeMethod::NAMES {
+        assert!(error.contains(name));
+        assert!(name.parse::<ResizeMethod>().is_ok());
+    }
+}
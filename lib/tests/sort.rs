@@ -0,0 +1,83 @@
+use std::cmp::Ordering;
+
+use dmi::{natural_cmp, Dmi, SortKey, SortOrder, State};
+
+fn dmi(states: &[(&str, bool)]) -> Dmi {
+    let mut dmi = Dmi::new("icon".to_string(), 32, 32);
+    for (name, movement) in states {
+        let mut state = State::new_blank(name.to_string(), 32, 32);
+        state.movement = *movement;
+        dmi.states.push(state);
+    }
+    dmi
+}
+
+fn names(dmi: &Dmi) -> Vec<(&str, bool)> {
+    dmi.states
+        .iter()
+        .map(|state| (state.name.as_str(), state.movement))
+        .collect()
+}
+
+#[test]
+fn natural() {
+    assert_eq!(natural_cmp("state2", "state10"), Ordering::Less);
+    assert_eq!(natural_cmp("state10", "state10b"), Ordering::Less);
+    assert_eq!(natural_cmp("a1b2", "a1b10"), Ordering::Less);
+    assert_eq!(natural_cmp("a01", "a1"), "a01".cmp("a1"));
+    assert_eq!(natural_cmp("b", "a9"), Ordering::Greater);
+    assert_eq!(natural_cmp("", "a"), Ordering::Less);
+}
+
+#[test]
+fn sort_states() {
+    let states = [
+        ("state10", false),
+        ("walk", true),
+        ("state2", false),
+        ("walk", false),
+        ("idle", false),
+    ];
+
+    let mut natural = dmi(&states);
+    natural.sort_states(SortKey::default());
+    assert_eq!(
+        names(&natural),
+        vec![
+            ("idle", false),
+            ("state2", false),
+            ("state10", false),
+            ("walk", true),
+            ("walk", false),
+        ]
+    );
+
+    let mut alphabetical = dmi(&states);
+    alphabetical.sort_states(SortKey {
+        order: SortOrder::Alphabetical,
+        movement_last: true,
+    });
+    assert_eq!(
+        names(&alphabetical),
+        vec![
+            ("idle", false),
+            ("state10", false),
+            ("state2", false),
+            ("walk", false),
+            ("walk", true),
+        ]
+    );
+
+    let mut custom = dmi(&states);
+    custom.sort_states_by(|a, b| a.name.len().cmp(&b.name.len()));
+    assert_eq!(
+        names(&custom),
+        vec![
+            ("walk", true),
+            ("walk", false),
+            ("idle", false),
+            ("state2", false),
+            ("state10", false),
+        ]
+    );
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use dmi::{Dmi, DmiError, SplitRule, State};
+
+fn items() -> Dmi {
+    let mut dmi = Dmi::new("items".to_string(), 32, 32);
+    for name in [
+        "gun_pistol",
+        "food_apple",
+        "gun_rifle",
+        "crate",
+        "food_bread",
+    ] {
+        dmi.states.push(State::new_blank(name.to_string(), 32, 32));
+    }
+    dmi
+}
+
+fn contents(dmis: &[Dmi]) -> Vec<(&str, Vec<&str>)> {
+    dmis.iter()
+        .map(|dmi| {
+            (
+                dmi.name.as_str(),
+                dmi.states.iter().map(|state| state.name.as_str()).collect(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn prefix() {
+    let dmi = items();
+    let (dmis, report) = dmi.split_by(&SplitRule::Prefix(vec![
+        "gun_".to_string(),
+        "food_".to_string(),
+    ]));
+
+    assert_eq!(
+        contents(&dmis),
+        vec![
+            ("gun", vec!["gun_pistol", "gun_rifle"]),
+            ("food", vec!["food_apple", "food_bread"]),
+            ("items", vec!["crate"]),
+        ]
+    );
+    assert!(dmis.iter().all(|dmi| (dmi.width, dmi.height) == (32, 32)));
+
+    assert_eq!(report.moves.len(), 5);
+    assert_eq!(
+        report.moves[1],
+        ("food_apple".to_string(), "food".to_string())
+    );
+    assert_eq!(report.moved_from("items").count(), 4);
+}
+
+#[test]
+fn regex() {
+    let dmi = items();
+    let (dmis, _) = dmi.split_by(&SplitRule::regex(r"^(?<dmi>[a-z]+)_").unwrap());
+    assert_eq!(
+        contents(&dmis),
+        vec![
+            ("gun", vec!["gun_pistol", "gun_rifle"]),
+            ("food", vec!["food_apple", "food_bread"]),
+            ("items", vec!["crate"]),
+        ]
+    );
+
+    let (dmis, _) = dmi.split_by(&SplitRule::regex(r"_(rifle|bread)$").unwrap());
+    assert_eq!(
+        contents(&dmis),
+        vec![
+            ("items", vec!["gun_pistol", "food_apple", "crate"]),
+            ("rifle", vec!["gun_rifle"]),
+            ("bread", vec!["food_bread"]),
+        ]
+    );
+
+    assert!(matches!(SplitRule::regex("("), Err(DmiError::Regex(_))));
+}
+
+#[test]
+fn mapping() {
+    let mut dmi = items();
+    let mut moving = State::new_blank("gun_rifle".to_string(), 32, 32);
+    moving.movement = true;
+    dmi.states.push(moving);
+
+    let mapping = HashMap::from([
+        ("crate".to_string(), "structures".to_string()),
+        ("gun_rifle".to_string(), "guns".to_string()),
+    ]);
+    let (dmis, report) = dmi.split_by(&SplitRule::Mapping(mapping));
+
+    assert_eq!(
+        contents(&dmis),
+        vec![
+            ("items", vec!["gun_pistol", "food_apple", "food_bread"]),
+            ("guns", vec!["gun_rifle", "gun_rifle"]),
+            ("structures", vec!["crate"]),
+        ]
+    );
+    assert!(dmis[1].states[1].movement);
+    assert_eq!(
+        report.moved_from("items").cloned().collect::<Vec<_>>(),
+        vec![
+            ("gun_rifle".to_string(), "guns".to_string()),
+            ("crate".to_string(), "structures".to_string()),
+            ("gun_rifle".to_string(), "guns".to_string()),
+        ]
+    );
+}
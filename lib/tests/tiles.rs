@@ -0,0 +1,90 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use dmi::{parse_tile_name, tile_name, Dmi, DmiError, State, DEFAULT_TILE_NAMING};
+
+/// Every pixel holds its own position so tiles can be told apart.
+fn gradient(width: u32, height: u32, frame: u8) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+        Rgba([x as u8, y as u8, frame, 255])
+    }))
+}
+
+fn large_icon() -> Dmi {
+    let mut dmi = Dmi::new("large".to_string(), 4, 2);
+
+    let mut state = State::new_blank("door_x_open".to_string(), 4, 2);
+    state.dirs = 4;
+    state.frame_count = 2;
+    state.frames = (0..8).map(|index| gradient(4, 2, index)).collect();
+    state.delays = vec![1., 2.];
+    state.hotspots = vec!["0,0,1".to_string(), "3,1,1".to_string()];
+    dmi.states.push(state);
+
+    dmi
+}
+
+#[test]
+fn tile_names() {
+    assert_eq!(tile_name(DEFAULT_TILE_NAMING, "wall", 1, 0), "wall_x1_y0");
+
+    assert_eq!(
+        parse_tile_name(DEFAULT_TILE_NAMING, "door_x_open_x12_y3"),
+        Some(("door_x_open".to_string(), 12, 3))
+    );
+    assert_eq!(
+        parse_tile_name("{x}-{y}/{state}", "1-2/door"),
+        Some(("door".to_string(), 1, 2))
+    );
+    assert_eq!(parse_tile_name(DEFAULT_TILE_NAMING, "door"), None);
+}
+
+#[test]
+fn split_and_join() {
+    let mut dmi = large_icon();
+
+    dmi.split_tiles(2, 2, DEFAULT_TILE_NAMING).unwrap();
+
+    assert_eq!((dmi.width, dmi.height), (2, 2));
+    assert_eq!(dmi.states.len(), 2);
+
+    let right = &dmi.states[1];
+    assert_eq!(right.name, "door_x_open_x1_y0");
+    assert_eq!(right.dirs, 4);
+    assert_eq!(right.frame_count, 2);
+    assert_eq!(right.delays, vec![1., 2.]);
+    assert_eq!(right.hotspots, vec!["1,1,1".to_string()]);
+    assert_eq!(dmi.states[0].hotspots, vec!["0,0,1".to_string()]);
+    assert_eq!(
+        *right.frames[5].to_rgba8().get_pixel(0, 1),
+        Rgba([2, 1, 5, 255])
+    );
+
+    dmi.join_tiles(DEFAULT_TILE_NAMING).unwrap();
+
+    let original = large_icon();
+    assert_eq!((dmi.width, dmi.height), (4, 2));
+    assert_eq!(dmi.states.len(), 1);
+
+    let state = &dmi.states[0];
+    assert_eq!(state.name, "door_x_open");
+    assert_eq!(state.hotspots, original.states[0].hotspots);
+    for (joined, original) in state.frames.iter().zip(original.states[0].frames.iter()) {
+        assert_eq!(joined.to_rgba8(), original.to_rgba8());
+    }
+}
+
+#[test]
+fn tile_errors() {
+    let mut dmi = large_icon();
+    assert!(matches!(
+        dmi.split_tiles(3, 2, DEFAULT_TILE_NAMING),
+        Err(DmiError::InvalidTileSize { .. })
+    ));
+
+    dmi.split_tiles(2, 2, DEFAULT_TILE_NAMING).unwrap();
+    dmi.states.remove(0);
+    assert!(matches!(
+        dmi.join_tiles(DEFAULT_TILE_NAMING),
+        Err(DmiError::MissingTile { x: 0, y: 0, .. })
+    ));
+}
@@ -23,6 +23,7 @@
 --- @field loading boolean Whether the editor is currently loading a file.
 --- @field modified boolean Whether a state has been modified.
 --- @field closed boolean Whether the editor has been closed.
+--- @field palette_check { file: string, tolerance: number }|nil The palette the DMI is checked against before saving.
 Editor = {}
 Editor.__index = Editor
 
@@ -51,6 +52,7 @@ function Editor.new(title, dmi)
 	self.context_widget   = nil
 	self.save_path        = nil
 	self.open_path        = is_filename and dmi --[[@as string]] or nil
+	self.palette_check    = nil
 
 	self.canvas_width     = 185
 	self.canvas_height    = 215
@@ -270,11 +272,39 @@ end
 function Editor:save()
 	if not self.dmi then return false end
 
+	if self.palette_check then
+		local violations, error = libdmi.palette_violations(self.dmi, self.palette_check.file,
+			self.palette_check.tolerance)
+
+		if error then
+			app.alert { title = "Error", text = { "Failed to check the palette", error } }
+			return false
+		end
+
+		if #violations > 0 then
+			local result = app.alert {
+				title = "Palette Check",
+				text = #violations .. " pixels use colors that are not in the palette",
+				buttons = { "&Snap and Save", "Save &Anyway", "&Cancel" }
+			}
+
+			if result == 1 then
+				if not self:snap_to_palette(self.palette_check.file, self.palette_check.tolerance) then
+					return false
+				end
+			elseif result ~= 2 then
+				return false
+			end
+		end
+	end
+
 	local path = self:path()
 	local filename, error = libdmi.save_dialog("Save File", app.fs.fileTitle(path), app.fs.filePath(path))
 	if #filename > 0 and not error then
 		self.save_path = filename
-		local _, error = libdmi.save_file(self.dmi, filename --[[@as string]])
+		local _, error = libdmi.save_file(self.dmi, filename --[[@as string]], {
+			clear_transparent = preferences.clear_transparent == true,
+		})
 		if not error then
 			self.modified = false
 		end
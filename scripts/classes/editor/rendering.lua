@@ -321,6 +321,12 @@ function Editor:onmouseup(ev)
 						Rectangle(ev.x, ev.y, 0, 0),
 						{
 							{ text = "Paste", onclick = function() self:paste_state() end },
+							{ text = "Import States...", onclick = function() self:import_states() end },
+							{ text = "Sort States...", onclick = function() self:sort_states() end },
+							{ text = "Palette Check...", onclick = function() self:palette_check_dialog() end },
+							{ text = "Save Options...", onclick = function() self:save_options_dialog() end },
+							{ text = "Collapse Directions", onclick = function() self:collapse_dirs() end },
+							{ text = "Smoothing Junctions", onclick = function() self:smoothing_junctions() end },
 						}
 					)
 				end
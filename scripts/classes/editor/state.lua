@@ -28,6 +28,84 @@ function Editor.new_file(plugin_path)
 	end
 end
 
+--- Renders a GAGS config with the given colors and opens the result in a new DMI Editor.
+--- The config, its icon file and the colors are asked with a dialog.
+--- @param plugin_path string Path where the extension is installed.
+function Editor.gags_preview(plugin_path)
+	local dialog = Dialog {
+		title = "GAGS Preview"
+	}
+
+	dialog:file {
+		id = "config",
+		label = "Config:",
+		title = "Select GAGS Config",
+		open = true,
+		filetypes = { "json" },
+		focus = true,
+	}
+
+	dialog:file {
+		id = "icon",
+		label = "Icon:",
+		title = "Select Greyscale Icon",
+		open = true,
+		filetypes = { "dmi" },
+	}
+
+	dialog:file {
+		id = "references",
+		label = "References:",
+		title = "Select GAGS References (Optional)",
+		open = true,
+		filetypes = { "json" },
+	}
+
+	dialog:entry {
+		id = "colors",
+		label = "Colors:",
+		text = "#ffffff",
+	}
+
+	dialog:button {
+		focus = true,
+		text = "&OK",
+		onclick = function()
+			local config = dialog.data.config --[[@as string]]
+			local icon = dialog.data.icon --[[@as string]]
+
+			if #config == 0 or #icon == 0 then
+				app.alert { title = "Warning", text = "Select a config and an icon file", buttons = { "&OK" } }
+				return
+			end
+
+			loadlib(plugin_path)
+
+			local references = dialog.data.references --[[@as string]]
+			if #references == 0 then
+				references = nil
+			end
+
+			local dmi, error = libdmi.render_gags(config, icon, dialog.data.colors, TEMP_DIR, references)
+			if not error then
+				dialog:close()
+				Editor.new(DIALOG_NAME, dmi --[[@as Dmi]])
+			else
+				app.alert { title = "Error", text = { "Failed to render config", error } }
+			end
+		end
+	}
+
+	dialog:button {
+		text = "&Cancel",
+		onclick = function()
+			dialog:close()
+		end
+	}
+
+	dialog:show()
+end
+
 --- Opens a state in the Aseprite editor by creating a new sprite and populating it with frames and layers based on the provided state.
 ---@param state State The state to be opened.
 function Editor:open_state(state)
@@ -133,6 +211,9 @@ function Editor:state_context(state, ev)
 			{ text = "Properties", onclick = function() self:state_properties(state) end },
 			{ text = "Open",       onclick = function() self:open_state(state) end },
 			{ text = "Copy",       onclick = function() self:copy_state(state) end },
+			{ text = "Directions", onclick = function() self:generate_directions(state) end },
+			{ text = "Color Matrix", onclick = function() self:color_matrix(state) end },
+			{ text = "Emissive",   onclick = function() self:extract_emissive(state) end },
 			{ text = "Remove",     onclick = function() self:remove_state(state) end },
 		}
 	)
@@ -351,15 +432,7 @@ end
 --- Copies a state to the clipboard.
 --- @param state State The state to be copied.
 function Editor:copy_state(state)
-	for _, state_sprite in ipairs(self.open_sprites) do
-		if state_sprite.state == state then
-			if state_sprite.sprite.isModified then
-				app.alert { title = self.title, text = "Save the open sprite first" }
-				return
-			end
-			break
-		end
-	end
+	if not self:is_state_saved(state) then return end
 
 	libdmi.copy_state(state, self.dmi.temp)
 end
@@ -378,6 +451,338 @@ function Editor:paste_state()
 	end
 end
 
+--- Replaces a state in the DMI file with a state returned from the library.
+--- Re-opens the state if it was open in the Aseprite editor.
+--- @param state State The state to be replaced.
+--- @param new_state State The state replacing it.
+function Editor:replace_state(state, new_state)
+	local reopen = false
+	for i, state_sprite in ipairs(self.open_sprites) do
+		if state_sprite.state == state then
+			state_sprite.sprite:close()
+			table.remove(self.open_sprites, i)
+			reopen = true
+			break
+		end
+	end
+
+	self.modified = true
+	self.dmi.states[table.index_of(self.dmi.states, state)] = new_state
+	self.image_cache:remove(state.frame_key)
+	self.image_cache:load_state(self.dmi, new_state)
+	self:repaint_states()
+	self:gc_open_sprites()
+
+	if reopen then
+		self:open_state(new_state)
+	end
+end
+
+--- Checks if the open sprite of a state has unsaved changes and warns the user.
+--- @param state State The state to be checked.
+--- @return boolean saved Whether the state has no unsaved changes.
+function Editor:is_state_saved(state)
+	for _, state_sprite in ipairs(self.open_sprites) do
+		if state_sprite.state == state then
+			if state_sprite.sprite.isModified then
+				app.alert { title = self.title, text = "Save the open sprite first" }
+				return false
+			end
+			break
+		end
+	end
+	return true
+end
+
+--- Shows a dialog to generate missing directions of a state from existing ones.
+--- @param state State The state to generate directions for.
+function Editor:generate_directions(state)
+	if not self:is_state_saved(state) then return end
+
+	local presets = {
+		["Mirror East to West"] = "mirror_east_to_west",
+		["Mirror West to East"] = "mirror_west_to_east",
+		["Diagonals from nearest cardinal"] = "diagonals_from_cardinal",
+	}
+
+	local dialog = Dialog {
+		title = "Generate Directions"
+	}
+
+	dialog:combobox {
+		id = "preset",
+		label = "Preset:",
+		option = "Mirror East to West",
+		options = { "Mirror East to West", "Mirror West to East", "Diagonals from nearest cardinal" },
+	}
+
+	dialog:button {
+		focus = true,
+		text = "&OK",
+		onclick = function()
+			dialog:close()
+
+			local new_state, error = libdmi.generate_directions(state, self.dmi.temp, presets[dialog.data.preset])
+
+			if not error then
+				self:replace_state(state, new_state --[[@as State]])
+			else
+				app.alert { title = "Error", text = { "Failed to generate directions", error } }
+			end
+		end
+	}
+
+	dialog:button {
+		text = "&Cancel",
+		onclick = function()
+			dialog:close()
+		end
+	}
+
+	dialog:show()
+end
+
+--- Shows a dialog to apply a BYOND colour matrix to a state with a live preview.
+--- @param state State The state to be recoloured.
+function Editor:color_matrix(state)
+	if not self:is_state_saved(state) then return end
+
+	local icon = self.image_cache:get(state.frame_key)
+	local preview = icon:clone()
+
+	local dialog = Dialog {
+		title = "Color Matrix"
+	}
+
+	--- @return (number)[]|nil matrix The values of the matrix or nil if any of them is not a number.
+	local parse_matrix = function()
+		local values = {}
+		for _, value in ipairs(string.split(dialog.data.matrix, ",%s")) do
+			local number = tonumber(value)
+			if not number then
+				return nil
+			end
+			table.insert(values, number)
+		end
+		return values
+	end
+
+	local update_preview = function()
+		local matrix = parse_matrix()
+		if matrix then
+			local bytes = libdmi.apply_color_matrix(matrix, icon.width, icon.height, icon.bytes)
+			if bytes then
+				preview.bytes = bytes
+			end
+		end
+		dialog:repaint()
+	end
+
+	dialog:entry {
+		id = "matrix",
+		label = "Matrix:",
+		text = "1, 0, 0, 0, 1, 0, 0, 0, 1",
+		focus = true,
+		onchange = update_preview,
+	}
+
+	dialog:canvas {
+		id = "preview",
+		width = 96,
+		height = 96,
+		onpaint = function(ev)
+			local size = math.min(96 / icon.width, 96 / icon.height)
+			local width, height = icon.width * size, icon.height * size
+			ev.context:drawImage(
+				preview,
+				Rectangle(0, 0, icon.width, icon.height),
+				Rectangle((96 - width) / 2, (96 - height) / 2, width, height)
+			)
+		end,
+	}
+
+	dialog:check {
+		id = "new_state",
+		label = "New state:",
+		selected = false,
+		onclick = function()
+			dialog:modify { id = "name", enabled = dialog.data.new_state }
+		end,
+	}
+
+	dialog:entry {
+		id = "name",
+		label = "Name:",
+		text = state.name .. "_mapped",
+		enabled = false,
+	}
+
+	dialog:button {
+		focus = true,
+		text = "&OK",
+		onclick = function()
+			local matrix = parse_matrix()
+			if not matrix then
+				app.alert { title = "Warning", text = "The matrix must only contain numbers" }
+				return
+			end
+
+			local new_state, error = libdmi.map_colors(state, self.dmi.temp, matrix)
+
+			if error then
+				app.alert { title = "Error", text = { "Failed to apply the color matrix", error } }
+				return
+			end
+
+			dialog:close()
+
+			new_state = new_state --[[@as State]]
+
+			if dialog.data.new_state then
+				new_state.name = dialog.data.name
+				self.modified = true
+				table.insert(self.dmi.states, table.index_of(self.dmi.states, state) + 1, new_state)
+				self.image_cache:load_state(self.dmi, new_state)
+				self:repaint_states()
+			else
+				self:replace_state(state, new_state)
+			end
+		end
+	}
+
+	dialog:button {
+		text = "&Cancel",
+		onclick = function()
+			dialog:close()
+		end
+	}
+
+	dialog:show()
+end
+
+--- Shows a dialog to extract the glowing pixels of a state into a new emissive state.
+--- @param state State The source state.
+function Editor:extract_emissive(state)
+	if not self:is_state_saved(state) then return end
+
+	local mask_names = {} --[[@type string[] ]]
+	local masks = {} --[[@type table<string, State> ]]
+	for _, other in ipairs(self.dmi.states) do
+		if other ~= state and not masks[other.name] then
+			table.insert(mask_names, other.name)
+			masks[other.name] = other
+		end
+	end
+
+	local dialog = Dialog {
+		title = "Emissive"
+	}
+
+	local update_mode = function()
+		local mode = dialog.data.mode
+		dialog:modify { id = "threshold", visible = mode == "Brightness" }
+		dialog:modify { id = "color", visible = mode == "Color" }
+		dialog:modify { id = "tolerance", visible = mode == "Color" }
+		dialog:modify { id = "mask", visible = mode == "Mask" }
+	end
+
+	dialog:combobox {
+		id = "mode",
+		label = "Select by:",
+		option = "Brightness",
+		options = #mask_names > 0 and { "Brightness", "Color", "Mask" } or { "Brightness", "Color" },
+		onchange = update_mode,
+	}
+
+	dialog:slider {
+		id = "threshold",
+		label = "Threshold:",
+		value = 200,
+		min = 0,
+		max = 255,
+	}
+
+	dialog:color {
+		id = "color",
+		label = "Color:",
+		color = app.fgColor,
+		visible = false,
+	}
+
+	dialog:slider {
+		id = "tolerance",
+		label = "Tolerance:",
+		value = 0,
+		min = 0,
+		max = 255,
+		visible = false,
+	}
+
+	dialog:combobox {
+		id = "mask",
+		label = "Mask state:",
+		option = mask_names[1],
+		options = mask_names,
+		visible = false,
+	}
+
+	dialog:check {
+		id = "white",
+		label = "Output:",
+		text = "White",
+		selected = true,
+	}
+
+	dialog:entry {
+		id = "suffix",
+		label = "Suffix:",
+		text = "_emissive",
+	}
+
+	dialog:button {
+		focus = true,
+		text = "&OK",
+		onclick = function()
+			local color = dialog.data.color --[[@as Color]]
+			local options = {
+				mode = string.lower(dialog.data.mode),
+				threshold = dialog.data.threshold,
+				colors = { { color.red, color.green, color.blue, color.alpha } },
+				tolerance = dialog.data.tolerance,
+				mask = masks[dialog.data.mask],
+				keep_colors = not dialog.data.white,
+				suffix = dialog.data.suffix,
+			}
+			if options.mode == "color" then
+				options.mode = "colors"
+			end
+
+			local new_state, error = libdmi.extract_emissive(self.dmi, table.index_of(self.dmi.states, state), options)
+
+			if error then
+				app.alert { title = "Error", text = { "Failed to extract the emissive state", error } }
+				return
+			end
+
+			dialog:close()
+
+			self.modified = true
+			table.insert(self.dmi.states, table.index_of(self.dmi.states, state) + 1, new_state)
+			self.image_cache:load_state(self.dmi, new_state --[[@as State]])
+			self:repaint_states()
+		end
+	}
+
+	dialog:button {
+		text = "&Cancel",
+		onclick = function()
+			dialog:close()
+		end
+	}
+
+	dialog:show()
+end
+
 --- Shows a dialog to resize the DMI file.
 function Editor:resize()
 	if not self.dmi then return end
@@ -534,7 +939,7 @@ function Editor:resize()
 		id = "method",
 		label = "Method:",
 		option = "Nearest-neighbor",
-		options = { "Nearest-neighbor", "Triangle", "CatmullRom", "Gaussian", "Lanczos3" },
+		options = { "Nearest-neighbor", "Triangle", "CatmullRom", "Gaussian", "Lanczos3", "Scale2x (EPX)", "Scale3x", "Scale4x", "xBR" },
 	}
 
 	dialog:button {
@@ -580,6 +985,14 @@ function Editor:resize()
 				method = "gaussian"
 			elseif method == "Lanczos3" then
 				method = "lanczos3"
+			elseif method == "Scale2x (EPX)" then
+				method = "scale2x"
+			elseif method == "Scale3x" then
+				method = "scale3x"
+			elseif method == "Scale4x" then
+				method = "scale4x"
+			elseif method == "xBR" then
+				method = "xbr2x"
 			end
 
 			local _, error = libdmi.resize(self.dmi, width, height, method)
@@ -615,6 +1028,15 @@ function Editor:crop()
 		title = "Crop"
 	}
 
+	--- Offset of a centered crop, as `resize_canvas` places it.
+	local center_offset = function(width, height)
+		local offset = libdmi.anchor_offset("center", original_width, original_height, width, height)
+		if not offset then
+			return dialog.data.left, dialog.data.top
+		end
+		return -offset.x, -offset.y
+	end
+
 	dialog:separator { text = "Size:" }
 
 	dialog:number {
@@ -632,7 +1054,7 @@ function Editor:crop()
 			end
 
 			if dialog.data.center then
-				left = math.floor((original_width - width) / 2)
+				left = center_offset(width, original_height)
 
 				dialog:modify {
 					id = "left",
@@ -666,7 +1088,7 @@ function Editor:crop()
 			end
 
 			if dialog.data.center then
-				top = math.floor((original_height - height) / 2)
+				top = select(2, center_offset(original_width, height))
 
 				dialog:modify {
 					id = "top",
@@ -727,8 +1149,7 @@ function Editor:crop()
 			local top = dialog.data.top
 
 			if dialog.data.center then
-				left = math.floor((original_width - width) / 2)
-				top = math.floor((original_height - height) / 2)
+				left, top = center_offset(width, height)
 			end
 
 			dialog:modify {
@@ -742,6 +1163,46 @@ function Editor:crop()
 		end
 	}
 
+	dialog:separator { text = "Preset:" }
+
+	dialog:button {
+		text = "&Fit to Content",
+		onclick = function()
+			local bounds, error = libdmi.content_bounds(self.dmi, dialog.data.center)
+
+			if error then
+				app.alert { title = "Error", text = { "Failed to find the content bounds", error } }
+				return
+			end
+
+			if not bounds then
+				app.alert { title = "Warning", text = "The DMI has no visible pixels" }
+				return
+			end
+
+			dialog:modify {
+				id = "width",
+				text = tostring(bounds.width)
+			}
+			dialog:modify {
+				id = "height",
+				text = tostring(bounds.height)
+			}
+			dialog:modify {
+				id = "left",
+				max = original_width - bounds.width,
+				value = bounds.x
+			}
+			dialog:modify {
+				id = "top",
+				max = original_height - bounds.height,
+				value = bounds.y
+			}
+		end
+	}
+
+	dialog:separator()
+
 	dialog:button {
 		focus = true,
 		text = "&OK",
@@ -781,11 +1242,17 @@ function Editor:crop()
 
 			dialog:close()
 
-			local _, error = libdmi.crop(self.dmi, left, top, width, height)
+			local hotspots, error
+			if dialog.data.center then
+				hotspots, error = libdmi.resize_canvas(self.dmi, width, height, "center")
+			else
+				hotspots, error = libdmi.resize_canvas(self.dmi, width, height, "custom", -left, -top)
+			end
 
 			if not error then
 				self.dmi.width = width
 				self.dmi.height = height
+				self:set_hotspots(hotspots --[[@as string[][] ]])
 				self:reload_open_states()
 			else
 				app.alert { title = "Error", text = { "Failed to crop", error } }
@@ -809,10 +1276,33 @@ function Editor:expand()
 	local original_width = self.dmi.width
 	local original_height = self.dmi.height
 
-	local dialog = Dialog {
+	local anchors = {
+		["Top-left"] = "top_left",
+		["Top-middle"] = "top",
+		["Top-right"] = "top_right",
+		["Middle-left"] = "left",
+		["Center"] = "center",
+		["Middle-right"] = "right",
+		["Bottom-left"] = "bottom_left",
+		["Bottom-middle"] = "bottom",
+		["Bottom-right"] = "bottom_right",
+		["Custom"] = "custom",
+	}
+
+	local dialog = Dialog {
 		title = "Expand"
 	}
 
+	--- Offset of the original canvas for the selected preset anchor, as `resize_canvas` places it.
+	local anchor_offset = function(width, height)
+		local offset = libdmi.anchor_offset(anchors[dialog.data.anchor], original_width, original_height, width,
+			height)
+		if not offset then
+			return dialog.data.left, dialog.data.top
+		end
+		return offset.x, offset.y
+	end
+
 	dialog:separator { text = "Size:" }
 
 	dialog:number {
@@ -827,13 +1317,7 @@ function Editor:expand()
 				local left = dialog.data.left
 
 				if dialog.data.anchor ~= "Custom" then
-					if string.find(dialog.data.anchor, "middle") or dialog.data.anchor == "Center" then
-						left = math.floor((width - original_width) / 2)
-					elseif string.find(dialog.data.anchor, "left") then
-						left = 0
-					elseif string.find(dialog.data.anchor, "right") then
-						left = width - original_width
-					end
+					left = anchor_offset(width, original_height)
 				end
 
 				dialog:modify {
@@ -856,13 +1340,7 @@ function Editor:expand()
 				local top = dialog.data.top
 
 				if dialog.data.anchor ~= "Custom" then
-					if string.find(dialog.data.anchor, "Middle") or dialog.data.anchor == "Center" then
-						top = math.floor((height - original_height) / 2)
-					elseif string.find(dialog.data.anchor, "Top") then
-						top = 0
-					elseif string.find(dialog.data.anchor, "Bottom") then
-						top = height - original_height
-					end
+					top = select(2, anchor_offset(original_width, height))
 				end
 
 				dialog:modify {
@@ -911,26 +1389,7 @@ function Editor:expand()
 		options = { "Top-left", "Top-middle", "Top-right", "Middle-left", "Center", "Middle-right", "Bottom-left", "Bottom-middle", "Bottom-right", "Custom" },
 		onchange = function()
 			if dialog.data.anchor ~= "Custom" then
-				local width = dialog.data.width
-				local height = dialog.data.height
-				local left = dialog.data.left
-				local top = dialog.data.top
-
-				if string.find(dialog.data.anchor, "middle") or dialog.data.anchor == "Center" then
-					left = math.floor((width - original_width) / 2)
-				elseif string.find(dialog.data.anchor, "left") then
-					left = 0
-				elseif string.find(dialog.data.anchor, "right") then
-					left = width - original_width
-				end
-
-				if string.find(dialog.data.anchor, "Middle") or dialog.data.anchor == "Center" then
-					top = math.floor((height - original_height) / 2)
-				elseif string.find(dialog.data.anchor, "Top") then
-					top = 0
-				elseif string.find(dialog.data.anchor, "Bottom") then
-					top = height - original_height
-				end
+				local left, top = anchor_offset(dialog.data.width, dialog.data.height)
 
 				dialog:modify {
 					id = "left",
@@ -978,11 +1437,17 @@ function Editor:expand()
 
 			dialog:close()
 
-			local _, error = libdmi.expand(self.dmi, left, top, width, height)
+			local hotspots, error
+			if dialog.data.anchor == "Custom" then
+				hotspots, error = libdmi.resize_canvas(self.dmi, width, height, "custom", left, top)
+			else
+				hotspots, error = libdmi.resize_canvas(self.dmi, width, height, anchors[dialog.data.anchor])
+			end
 
 			if not error then
 				self.dmi.width = width
 				self.dmi.height = height
+				self:set_hotspots(hotspots --[[@as string[][] ]])
 				self:reload_open_states()
 			else
 				app.alert { title = "Error", text = { "Failed to expand", error } }
@@ -1000,6 +1465,675 @@ function Editor:expand()
 	dialog:show()
 end
 
+--- Shows a dialog to replace a colour across every state of the DMI file.
+function Editor:replace_colors()
+	if not self.dmi then return end
+
+	local dialog = Dialog {
+		title = "Replace Colors"
+	}
+
+	local color_map = function()
+		local from = dialog.data.from --[[@as Color]]
+		local to = dialog.data.to --[[@as Color]]
+		return {
+			{
+				from = { from.red, from.green, from.blue, from.alpha },
+				to = { to.red, to.green, to.blue, to.alpha },
+			}
+		}
+	end
+
+	dialog:color {
+		id = "from",
+		label = "From:",
+		color = app.fgColor,
+	}
+
+	dialog:color {
+		id = "to",
+		label = "To:",
+		color = app.bgColor,
+	}
+
+	dialog:slider {
+		id = "tolerance",
+		label = "Tolerance:",
+		value = 0,
+		min = 0,
+		max = 255,
+	}
+
+	dialog:label {
+		id = "preview",
+		label = "Pixels:",
+		text = "-",
+	}
+
+	dialog:button {
+		text = "&Preview",
+		onclick = function()
+			local counts, error = libdmi.replace_colors(self.dmi, color_map(), dialog.data.tolerance, nil, true)
+
+			if error then
+				app.alert { title = "Error", text = { "Failed to preview", error } }
+				return
+			end
+
+			local pixels, states = 0, 0
+			for _, count in ipairs(counts --[[@as number[] ]]) do
+				if count > 0 then
+					pixels = pixels + count
+					states = states + 1
+				end
+			end
+
+			dialog:modify {
+				id = "preview",
+				text = math.floor(pixels) .. " in " .. states .. (states == 1 and " state" or " states")
+			}
+		end
+	}
+
+	dialog:separator()
+
+	dialog:button {
+		focus = true,
+		text = "&OK",
+		onclick = function()
+			local alert = app.alert {
+				title = "Warning",
+				text = {
+					"Replacing colors will re-open all open states",
+					"without saving and this is irreversible. Continue?"
+				},
+				buttons = { "&OK", "&Cancel" }
+			}
+
+			if alert == 2 then
+				return
+			end
+
+			dialog:close()
+
+			local _, error = libdmi.replace_colors(self.dmi, color_map(), dialog.data.tolerance)
+
+			if not error then
+				self.modified = true
+				self:reload_open_states()
+			else
+				app.alert { title = "Error", text = { "Failed to replace colors", error } }
+			end
+		end
+	}
+
+	dialog:button {
+		text = "&Cancel",
+		onclick = function()
+			dialog:close()
+		end
+	}
+
+	dialog:show()
+end
+
+--- Shows a dialog to import the states of another DMI file into this one.
+function Editor:import_states()
+	if not self.dmi then return end
+
+	local conflicts = { ["Rename"] = "rename", ["Replace"] = "replace", ["Skip"] = "skip" }
+	local sizes = { ["Expand"] = "expand", ["Scale"] = "scale", ["Cancel import"] = "reject" }
+
+	local dialog = Dialog {
+		title = "Import States"
+	}
+
+	dialog:file {
+		id = "file",
+		label = "File:",
+		title = "Select DMI File",
+		open = true,
+		filetypes = { "dmi" },
+		focus = true,
+	}
+
+	dialog:combobox {
+		id = "conflicts",
+		label = "Existing states:",
+		option = "Rename",
+		options = { "Rename", "Replace", "Skip" },
+	}
+
+	dialog:combobox {
+		id = "size",
+		label = "Different size:",
+		option = "Expand",
+		options = { "Expand", "Scale", "Cancel import" },
+	}
+
+	dialog:button {
+		focus = true,
+		text = "&OK",
+		onclick = function()
+			local file = dialog.data.file --[[@as string]]
+			if #file == 0 then
+				app.alert { title = "Warning", text = "Select a DMI file", buttons = { "&OK" } }
+				return
+			end
+
+			for _, state_sprite in ipairs(self.open_sprites) do
+				if state_sprite.sprite.isModified then
+					app.alert { title = self.title, text = "Save the open sprites first" }
+					return
+				end
+			end
+
+			local result, error = libdmi.merge(self.dmi, file, conflicts[dialog.data.conflicts], sizes[dialog.data.size])
+
+			if error then
+				app.alert { title = "Error", text = { "Failed to import states", error } }
+				return
+			end
+
+			dialog:close()
+
+			for _, state_sprite in ipairs(self.open_sprites) do
+				state_sprite.sprite:close()
+			end
+
+			self.open_sprites = {}
+			self.dmi = result.dmi
+			self.modified = true
+			self.image_cache:clear()
+			self.image_cache:load_previews(self.dmi)
+			self:repaint_states()
+
+			local text = { #result.added .. " added, " .. #result.replaced .. " replaced, " .. #result.skipped .. " skipped" }
+			for _, rename in ipairs(result.renamed) do
+				table.insert(text, "\"" .. rename.from .. "\" renamed to \"" .. rename.to .. "\"")
+			end
+			app.alert { title = "Import States", text = text }
+		end
+	}
+
+	dialog:button {
+		text = "&Cancel",
+		onclick = function()
+			dialog:close()
+		end
+	}
+
+	dialog:show()
+end
+
+--- Shows a dialog for loading the colors of the whole DMI into the palette of the active sprite,
+--- or exporting them to a palette file.
+function Editor:dmi_palette()
+	if not self.dmi then return end
+
+	local sprite = app.sprite
+	if not sprite then return end
+
+	local palette, error = libdmi.palette(self.dmi)
+	if error then
+		app.alert { title = "Error", text = { "Failed to read the palette", error } }
+		return
+	end
+
+	local colors = palette --[[@as { red: number, green: number, blue: number, alpha: number, count: number }[] ]]
+
+	local dialog = Dialog {
+		title = "DMI Palette"
+	}
+
+	dialog:label {
+		label = "Colors:",
+		text = tostring(#colors),
+	}
+
+	dialog:file {
+		id = "file",
+		label = "Export:",
+		title = "Export Palette",
+		save = true,
+		filename = self.dmi.name .. ".gpl",
+		filetypes = { "gpl", "pal", "act" },
+	}
+
+	dialog:button {
+		focus = true,
+		text = "&Load into Sprite",
+		onclick = function()
+			if #colors == 0 then
+				app.alert { title = "Warning", text = "The DMI has no visible colors", buttons = { "&OK" } }
+				return
+			end
+
+			local new_palette = Palette(#colors)
+			for index, color in ipairs(colors) do
+				new_palette:setColor(index - 1,
+					Color { red = color.red, green = color.green, blue = color.blue, alpha = color.alpha })
+			end
+
+			app.transaction("Load DMI Palette", function()
+				sprite:setPalette(new_palette)
+			end)
+
+			dialog:close()
+		end
+	}
+
+	dialog:button {
+		text = "&Export",
+		onclick = function()
+			local file = dialog.data.file --[[@as string]]
+			if #file == 0 then
+				app.alert { title = "Warning", text = "Select a palette file", buttons = { "&OK" } }
+				return
+			end
+
+			local _, error = libdmi.export_palette(self.dmi, file)
+			if error then
+				app.alert { title = "Error", text = { "Failed to export the palette", error } }
+				return
+			end
+
+			dialog:close()
+		end
+	}
+
+	dialog:button {
+		text = "&Cancel",
+		onclick = function()
+			dialog:close()
+		end
+	}
+
+	dialog:show()
+end
+
+--- Replaces the colors of the DMI that are not in the palette file with the nearest palette color.
+--- @param file string The path of the palette file.
+--- @param tolerance number How much each channel may differ from a palette color.
+--- @return boolean success Whether the DMI was changed.
+function Editor:snap_to_palette(file, tolerance)
+	for _, state_sprite in ipairs(self.open_sprites) do
+		if state_sprite.sprite.isModified then
+			app.alert { title = self.title, text = "Save the open sprites first" }
+			return false
+		end
+	end
+
+	local dmi, error = libdmi.snap_to_palette(self.dmi, file, tolerance)
+
+	if error then
+		app.alert { title = "Error", text = { "Failed to snap to the palette", error } }
+		return false
+	end
+
+	for _, state_sprite in ipairs(self.open_sprites) do
+		state_sprite.sprite:close()
+	end
+
+	self.open_sprites = {}
+	self.dmi = dmi --[[@as Dmi]]
+	self.modified = true
+	self.image_cache:clear()
+	self.image_cache:load_previews(self.dmi)
+	self:repaint_states()
+
+	return true
+end
+
+--- Shows a dialog for checking the DMI against a palette file, snapping off-palette colors
+--- and checking the palette on every save.
+function Editor:palette_check_dialog()
+	if not self.dmi then return end
+
+	local dialog = Dialog {
+		title = "Palette Check"
+	}
+
+	dialog:file {
+		id = "file",
+		label = "Palette:",
+		title = "Select Palette File",
+		open = true,
+		filename = self.palette_check and self.palette_check.file or "",
+		filetypes = { "gpl", "pal", "act" },
+		focus = true,
+	}
+
+	dialog:slider {
+		id = "tolerance",
+		label = "Tolerance:",
+		value = self.palette_check and self.palette_check.tolerance or 0,
+		min = 0,
+		max = 255,
+	}
+
+	dialog:check {
+		id = "on_save",
+		label = "Check before saving:",
+		selected = self.palette_check ~= nil,
+	}
+
+	dialog:label {
+		id = "result",
+		label = "Off-palette pixels:",
+		text = "-",
+	}
+
+	local selected_file = function()
+		local file = dialog.data.file --[[@as string]]
+		if #file == 0 then
+			app.alert { title = "Warning", text = "Select a palette file", buttons = { "&OK" } }
+			return nil
+		end
+		return file
+	end
+
+	dialog:button {
+		text = "&Check",
+		onclick = function()
+			local file = selected_file()
+			if not file then return end
+
+			local violations, error = libdmi.palette_violations(self.dmi, file, dialog.data.tolerance)
+
+			if error then
+				app.alert { title = "Error", text = { "Failed to check the palette", error } }
+				return
+			end
+
+			local text = tostring(#violations)
+			local first = violations[1]
+			if first then
+				text = text .. " (" .. first.name .. ", dir " .. first.dir .. ", frame " .. first.frame .. ", "
+					.. first.x .. "," .. first.y .. ", "
+					.. string.format("#%02x%02x%02x%02x", first.red, first.green, first.blue, first.alpha) .. ")"
+			end
+
+			dialog:modify {
+				id = "result",
+				text = text,
+			}
+		end
+	}
+
+	dialog:button {
+		text = "&Snap",
+		onclick = function()
+			local file = selected_file()
+			if not file then return end
+
+			if self:snap_to_palette(file, dialog.data.tolerance) then
+				dialog:modify {
+					id = "result",
+					text = "0",
+				}
+			end
+		end
+	}
+
+	dialog:separator()
+
+	dialog:button {
+		focus = true,
+		text = "&OK",
+		onclick = function()
+			if dialog.data.on_save then
+				local file = selected_file()
+				if not file then return end
+				self.palette_check = { file = file, tolerance = dialog.data.tolerance }
+			else
+				self.palette_check = nil
+			end
+
+			dialog:close()
+		end
+	}
+
+	dialog:button {
+		text = "&Cancel",
+		onclick = function()
+			dialog:close()
+		end
+	}
+
+	dialog:show()
+end
+
+--- Shows a dialog for the options used when saving DMI files.
+function Editor:save_options_dialog()
+	local dialog = Dialog {
+		title = "Save Options"
+	}
+
+	dialog:check {
+		id = "clear_transparent",
+		label = "Clear transparent pixels:",
+		selected = preferences.clear_transparent == true,
+	}
+
+	dialog:separator()
+
+	dialog:button {
+		focus = true,
+		text = "&OK",
+		onclick = function()
+			preferences.clear_transparent = dialog.data.clear_transparent
+			dialog:close()
+		end
+	}
+
+	dialog:button {
+		text = "&Cancel",
+		onclick = function()
+			dialog:close()
+		end
+	}
+
+	dialog:show()
+end
+
+--- Reduces the directions of states whose extra directions are identical to the ones BYOND shows instead.
+function Editor:collapse_dirs()
+	if not self.dmi then return end
+
+	for _, state_sprite in ipairs(self.open_sprites) do
+		if state_sprite.sprite.isModified then
+			app.alert { title = self.title, text = "Save the open sprites first" }
+			return
+		end
+	end
+
+	local result, error = libdmi.collapse_dirs(self.dmi)
+
+	if error then
+		app.alert { title = "Error", text = { "Failed to collapse directions", error } }
+		return
+	end
+
+	local report = result --[[@as { states: number, bytes_saved: number, dmi: Dmi? }]]
+
+	if not report.dmi then
+		app.alert { title = "Collapse Directions", text = "No state has redundant directions" }
+		return
+	end
+
+	for _, state_sprite in ipairs(self.open_sprites) do
+		state_sprite.sprite:close()
+	end
+
+	self.open_sprites = {}
+	self.dmi = report.dmi
+	self.modified = true
+	self.image_cache:clear()
+	self.image_cache:load_previews(self.dmi)
+	self:repaint_states()
+
+	app.alert {
+		title = "Collapse Directions",
+		text = "Collapsed " .. report.states .. " states, saving " .. report.bytes_saved .. " bytes"
+	}
+end
+
+--- Shows a dialog for sorting the states of the DMI.
+function Editor:sort_states()
+	if not self.dmi then return end
+
+	local orders = { ["Natural"] = "natural", ["Alphabetical"] = "alphabetical" }
+
+	local dialog = Dialog {
+		title = "Sort States"
+	}
+
+	dialog:combobox {
+		id = "order",
+		label = "Order:",
+		option = "Natural",
+		options = { "Natural", "Alphabetical" },
+	}
+
+	dialog:check {
+		id = "movement_last",
+		label = "Movement states last:",
+		selected = false,
+	}
+
+	dialog:button {
+		focus = true,
+		text = "&OK",
+		onclick = function()
+			local indexes, error = libdmi.sort_states(self.dmi, orders[dialog.data.order], dialog.data.movement_last)
+
+			if error then
+				app.alert { title = "Error", text = { "Failed to sort states", error } }
+				return
+			end
+
+			dialog:close()
+
+			local states = {} --[[@type State[] ]]
+			for _, index in ipairs(indexes --[[@as number[] ]]) do
+				table.insert(states, self.dmi.states[index])
+			end
+
+			self.dmi.states = states
+			self.modified = true
+			self:repaint_states()
+		end
+	}
+
+	dialog:button {
+		text = "&Cancel",
+		onclick = function()
+			dialog:close()
+		end
+	}
+
+	dialog:show()
+end
+
+--- Shows a dialog for generating smoothing junction states from the corner pieces of this DMI,
+--- or checking the DMI for missing junction states.
+function Editor:smoothing_junctions()
+	if not self.dmi then return end
+
+	local sets = { ["47 (Reduced)"] = "reduced", ["256 (Full)"] = "full" }
+
+	local dialog = Dialog {
+		title = "Smoothing Junctions"
+	}
+
+	dialog:entry {
+		id = "prefix",
+		label = "Prefix:",
+		text = self.dmi.name,
+		focus = true,
+	}
+
+	dialog:entry {
+		id = "naming",
+		label = "Naming:",
+		text = "{prefix}-{bitmask}",
+	}
+
+	dialog:combobox {
+		id = "set",
+		label = "Junctions:",
+		option = "47 (Reduced)",
+		options = { "47 (Reduced)", "256 (Full)" },
+	}
+
+	dialog:label {
+		id = "missing",
+		label = "Missing:",
+		text = "-",
+	}
+
+	dialog:button {
+		text = "&Check",
+		onclick = function()
+			local missing, error = libdmi.missing_junctions(self.dmi, dialog.data.prefix, dialog.data.naming,
+				sets[dialog.data.set])
+
+			if error then
+				app.alert { title = "Error", text = { "Failed to check junctions", error } }
+				return
+			end
+
+			local text = #missing == 0 and "None" or table.concat(missing --[[@as number[] ]], ", ")
+			dialog:modify {
+				id = "missing",
+				text = #text > 40 and (#missing .. " states") or text
+			}
+		end
+	}
+
+	dialog:separator()
+
+	dialog:button {
+		focus = true,
+		text = "&Generate",
+		onclick = function()
+			local dmi, error = libdmi.cut_junctions(self.dmi, dialog.data.prefix, dialog.data.naming,
+				sets[dialog.data.set], TEMP_DIR)
+
+			if not error then
+				dialog:close()
+				Editor.new(DIALOG_NAME, dmi --[[@as Dmi]])
+			else
+				app.alert { title = "Error", text = { "Failed to generate junctions", error } }
+			end
+		end
+	}
+
+	dialog:button {
+		text = "&Cancel",
+		onclick = function()
+			dialog:close()
+		end
+	}
+
+	dialog:show()
+end
+
+--- Replaces the hotspots of the states after the canvas changed, warning about the removed ones.
+--- @param hotspots string[][] The hotspots of every state in order.
+function Editor:set_hotspots(hotspots)
+	local removed = 0
+	for index, state in ipairs(self.dmi.states) do
+		removed = removed + #state.hotspots - #hotspots[index]
+		state.hotspots = hotspots[index]
+	end
+
+	if removed > 0 then
+		app.alert { title = "Warning", text = removed .. " hotspot(s) fell outside the new canvas and were removed" }
+	end
+end
+
 --- Reloads all open states in the editor.
 function Editor:reload_open_states()
 	local open_states = {} --[[@type State[] ]]
@@ -19,6 +19,10 @@ open_editors = {}
 --- @type LibDmi
 libdmi = nil
 
+--- Plugin preferences, saved between sessions.
+--- @type { clear_transparent: boolean? }
+preferences = {}
+
 --- Initializes the plugin. Called when the plugin is loaded.
 --- @param plugin Plugin The plugin object.
 function init(plugin)
@@ -30,6 +34,8 @@ function init(plugin)
 		return
 	end
 
+	preferences = plugin.preferences
+
 	after_listener = app.events:on("aftercommand", function(ev)
 		if ev.name == "OpenFile" then
 			if app.sprite and app.sprite.filename:ends_with(".dmi") then
@@ -90,6 +96,15 @@ function init(plugin)
 		end,
 	}
 
+	plugin:newCommand {
+		id = "dmi_gags_preview",
+		title = "GAGS Preview",
+		group = "dmi_editor",
+		onclick = function()
+			Editor.gags_preview(plugin.path)
+		end,
+	}
+
 	plugin:newMenuSeparator {
 		group = "dmi_editor",
 	}
@@ -139,6 +154,36 @@ function init(plugin)
 		end,
 	}
 
+	plugin:newCommand {
+		id = "dmi_replace_colors",
+		title = "Replace Colors",
+		group = "dmi_editor",
+		onclick = function()
+			local state_sprite = is_state_sprite()
+			if state_sprite then
+				state_sprite.editor:replace_colors()
+			end
+		end,
+		onenabled = function()
+			return is_state_sprite() and true or false
+		end,
+	}
+
+	plugin:newCommand {
+		id = "dmi_palette",
+		title = "DMI Palette",
+		group = "dmi_editor",
+		onclick = function()
+			local state_sprite = is_state_sprite()
+			if state_sprite then
+				state_sprite.editor:dmi_palette()
+			end
+		end,
+		onenabled = function()
+			return is_state_sprite() and true or false
+		end,
+	}
+
 	plugin:newMenuSeparator {
 		group = "dmi_editor",
 	}
@@ -799,17 +799,36 @@ WebSocketMessageType = {
 --- @class LibDmi: table
 --- @field new_file fun(name: string, width: number, height: number, temp: string): Dmi?, string? Creates a new DMI file. If fails, returns nil and an error message.
 --- @field open_file fun(path: string, temp: string): Dmi?, string? Opens a DMI file. If fails, returns nil and an error message.
---- @field save_file fun(dmi: Dmi, filename: string): nil, string? Saves the DMI file. If fails, returns an error message.
+--- @field save_file fun(dmi: Dmi, filename: string, options?: { clear_transparent: boolean? }): nil, string? Saves the DMI file. `clear_transparent` zeroes the RGB of fully transparent pixels. If fails, returns an error message.
 --- @field new_state fun(width: number, height: number, temp: string): State?, string? Creates a new state. If fails, returns nil and an error message.
 --- @field copy_state fun(state: State, temp: string): nil, string? Copies the state to the clipboard. If fails, returns an error message.
 --- @field paste_state fun(width: number, height: number, temp: string): State?, string? Pastes the state from the clipboard. If fails, returns nil and an error message.
---- @field resize fun(dmi: Dmi, width: number, height: number, medhod: string): nil, string? Resizes the DMI file. If fails, returns an error message.
+--- @field resize fun(dmi: Dmi, width: number, height: number, method: "nearest"|"triangle"|"catmullrom"|"gaussian"|"lanczos3"|"scale2x"|"scale3x"|"scale4x"|"xbr2x"): nil, string? Resizes the DMI file. If fails or the method is unknown, returns an error message.
 --- @field crop fun(dmi: Dmi, x: number, y: number, width: number, height: number): nil, string? Crops the DMI file. If fails, returns an error message.
 --- @field expand fun(dmi: Dmi, x: number, y: number, width: number, height: number): nil, string? Expands the DMI file size. If fails, returns an error message.
+--- @field resize_canvas fun(dmi: Dmi, width: number, height: number, anchor: "top_left"|"top"|"top_right"|"left"|"center"|"right"|"bottom_left"|"bottom"|"bottom_right"|"custom", x?: number, y?: number): (string[][])?, string? Expands or crops the DMI file around an anchor, `x` and `y` are used by the custom anchor. Returns the moved hotspots of every state, hotspots outside the new canvas are removed. If fails, returns nil and an error message.
+--- @field anchor_offset fun(anchor: "top_left"|"top"|"top_right"|"left"|"center"|"right"|"bottom_left"|"bottom"|"bottom_right", width: number, height: number, new_width: number, new_height: number): { x: number, y: number }?, string? Returns where the old canvas is placed on the new canvas when resizing around the anchor. If fails, returns nil and an error message.
+--- @field generate_directions fun(state: State, temp: string, preset: "mirror_east_to_west"|"mirror_west_to_east"|"diagonals_from_cardinal"): State?, string? Generates missing directions of the state. If fails, returns nil and an error message.
+--- @field content_bounds fun(dmi: Dmi, symmetric?: boolean, scope?: Scope): Rect?, string? Returns the smallest rectangle containing every visible pixel or nil if there are none. If fails, returns nil and an error message.
+--- @field trim fun(dmi: Dmi, symmetric?: boolean, scope?: Scope): TrimResult?, string? Crops the DMI file to the content bounds of the scope and returns the applied rectangle with the moved hotspots of every state. If fails, returns nil and an error message.
+--- @field replace_colors fun(dmi: Dmi, map: ColorPair[], tolerance?: number, scope?: Scope, preview?: boolean): (number[])?, string? Replaces colors and returns the number of changed pixels per state, nothing is written in preview mode. If fails, returns nil and an error message.
+--- @field map_colors fun(state: State, temp: string, matrix: (number)[]): State?, string? Applies a BYOND colour matrix of 9, 12, 16 or 20 values to a copy of the state. If fails, returns nil and an error message.
+--- @field extract_emissive fun(dmi: Dmi, index: number, options: EmissiveOptions): State?, string? Returns a copy of the state at the index holding only the selected pixels, named with the suffix. If fails, returns nil and an error message.
+--- @field merge fun(dmi: Dmi, path: string, conflicts: "rename"|"replace"|"skip"|"error", size: "expand"|"scale"|"reject", option?: string): ({ dmi: Dmi, added: string[], replaced: string[], skipped: string[], renamed: { from: string, to: string }[] })?, string? Imports the states of another DMI file and returns the rewritten DMI with the names of the added, replaced, skipped and renamed states. `option` is the anchor when expanding or the resize method when scaling. If fails, returns nil and an error message.
+--- @field sort_states fun(dmi: Dmi, order: "alphabetical"|"natural", movement_last: boolean): (number[])?, string? Returns the indexes of the states in sorted order. States that compare equal keep their order. If fails, returns nil and an error message.
+--- @field palette fun(dmi: Dmi): ({ red: number, green: number, blue: number, alpha: number, count: number }[])?, string? Returns the distinct visible colors of the DMI with their usage counts, most used first. If fails, returns nil and an error message.
+--- @field export_palette fun(dmi: Dmi, filename: string): nil, string? Saves the palette of the DMI as a GIMP `.gpl`, JASC `.pal` or Adobe `.act` file, picked by the extension. If fails, returns an error message.
+--- @field palette_violations fun(dmi: Dmi, palette: string, tolerance?: number): ({ state: number, name: string, dir: number, frame: number, x: number, y: number, red: number, green: number, blue: number, alpha: number }[])?, string? Returns the visible pixels whose colors are not in the palette file within the tolerance, with their colors. If fails, returns nil and an error message.
+--- @field snap_to_palette fun(dmi: Dmi, palette: string, tolerance?: number): Dmi?, string? Replaces the colors that are not in the palette file with the nearest palette color and returns the rewritten DMI. If fails, returns nil and an error message.
+--- @field collapse_dirs fun(dmi: Dmi): ({ states: number, bytes_saved: number, dmi: Dmi? })?, string? Reduces the directions of states whose extra directions look the same as the ones BYOND falls back to. Returns the number of changed states, the bytes saved and the rewritten DMI if any state changed. If fails, returns nil and an error message.
+--- @field render_gags fun(config: string, icon: string, colors: string, temp: string, references?: string): Dmi?, string? Renders every state of a GAGS config with colors like "#ff0000#00ff00". `references` is a JSON file mapping the reference types used by reference layers to their config and icon, like { "/datum/greyscale_config/stripe": { "config": "stripe.json", "icon": "stripe.dmi" } }. If fails, returns nil and an error message.
+--- @field cut_junctions fun(dmi: Dmi, prefix: string, naming: string, set: "reduced"|"full", temp: string): Dmi?, string? Builds smoothing junction states from the convex, concave, horizontal, vertical and flat states of the DMI. `{prefix}` and `{bitmask}` are replaced in the naming. If fails, returns nil and an error message.
+--- @field missing_junctions fun(dmi: Dmi, prefix: string, naming: string, set: "reduced"|"full"): (number[])?, string? Returns the bitmasks of the junction set that have no state in the DMI. If fails, returns nil and an error message.
+--- @field apply_color_matrix fun(matrix: (number)[], width: number, height: number, bytes: string): string?, string? Applies a BYOND colour matrix to the RGBA bytes of an image and returns the new bytes. If fails, returns nil and an error message.
 --- @field overlay_color fun(r: number, g: number, b: number, width: number, height: number, ...: number): ...: number|nil Overlays the given bytes of an image on a plain color.
 --- @field remove_dir fun(path: string, soft: boolean): nil, string? Removes a directory. If fails, returns an error message.
---- @field exists fun(path: string): boolean Returns true if the path points at an existing entity.
---- @field check_update fun(): boolean Return true if there is an update available.
+--- @field exists fun(path: string): boolean?, string? Returns true if the path points at an existing entity.
+--- @field check_update fun(): boolean?, string? Return true if there is an update available.
 --- @field instances fun(): number?, string? Return the number of Aseprite instances running.
 --- @field save_dialog fun(title: string, filename: string, location: string): string?, string? Shows a save dialog. Returns the path of the file to save or empty string if the user cancels the dialog.
 --- @field open_repo fun(path?: string): nil, string? Opens the repository in the default browser. If fails, returns an error message.
@@ -831,3 +850,31 @@ WebSocketMessageType = {
 --- @field rewind boolean Whether the state rewinds or not.
 --- @field movement boolean Whether the state is a movement state or not.
 --- @field hotspots (string)[] The hotspots of the state.
+
+--- @class Rect: table
+--- @field x number The x coordinate of the rectangle.
+--- @field y number The y coordinate of the rectangle.
+--- @field width number The width of the rectangle.
+--- @field height number The height of the rectangle.
+
+--- @class TrimResult: Rect
+--- @field hotspots (string[])[] The hotspots of every state, moved with the trimmed canvas.
+
+--- @class Scope: table
+--- @field states? (number)[] Indexes of the selected states. All states if nil.
+--- @field frames? (number)[] Indexes of the selected frames. All frames if nil.
+--- @field dirs? (number)[] Indexes of the selected directions. All directions if nil.
+
+--- @class ColorPair: table
+--- @field from (number)[] The RGBA color to be replaced.
+--- @field to (number)[] The RGBA color to replace with.
+
+--- @class EmissiveOptions: table
+--- @field mode "brightness"|"colors"|"mask" How the glowing pixels are selected.
+--- @field threshold? number The minimum brightness of the brightness mode. Defaults to 200.
+--- @field colors? (number)[][] The RGBA colors of the colors mode.
+--- @field tolerance? number The color tolerance of the colors mode. Defaults to 0.
+--- @field mask? State The state whose visible pixels are selected in the mask mode.
+--- @field color? (number)[] The RGBA color of the selected pixels. Defaults to white.
+--- @field keep_colors? boolean Keeps the source colors of the selected pixels instead of `color`.
+--- @field suffix? string Appended to the name of the new state. Defaults to "_emissive".